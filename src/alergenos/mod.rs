@@ -0,0 +1,349 @@
+use crate::{IOrder, Item};
+
+/// Alergias y restricciones alimentarias que cocina debe ver destacadas.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Alergeno {
+    Gluten,
+    Mani,
+    FrutosSecos,
+    Lactosa,
+    Huevo,
+    Mariscos,
+    Pescado,
+    Soya,
+    Vegetariano,
+    Vegano,
+}
+
+const TODOS: [Alergeno; 10] = [
+    Alergeno::Gluten,
+    Alergeno::Mani,
+    Alergeno::FrutosSecos,
+    Alergeno::Lactosa,
+    Alergeno::Huevo,
+    Alergeno::Mariscos,
+    Alergeno::Pescado,
+    Alergeno::Soya,
+    Alergeno::Vegetariano,
+    Alergeno::Vegano,
+];
+
+impl Alergeno {
+    /// Texto que se imprime en la banda de advertencia.
+    pub fn etiqueta(&self) -> &'static str {
+        match self {
+            Alergeno::Gluten => "SIN GLUTEN",
+            Alergeno::Mani => "ALERGIA AL MANÍ",
+            Alergeno::FrutosSecos => "ALERGIA FRUTOS SECOS",
+            Alergeno::Lactosa => "SIN LACTOSA",
+            Alergeno::Huevo => "ALERGIA AL HUEVO",
+            Alergeno::Mariscos => "ALERGIA MARISCOS",
+            Alergeno::Pescado => "ALERGIA PESCADO",
+            Alergeno::Soya => "ALERGIA SOYA",
+            Alergeno::Vegetariano => "VEGETARIANO",
+            Alergeno::Vegano => "VEGANO",
+        }
+    }
+
+    /// Nombre del alérgeno para la banda de menciones.
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            Alergeno::Gluten => "GLUTEN",
+            Alergeno::Mani => "MANÍ",
+            Alergeno::FrutosSecos => "FRUTOS SECOS",
+            Alergeno::Lactosa => "LÁCTEOS",
+            Alergeno::Huevo => "HUEVO",
+            Alergeno::Mariscos => "MARISCOS",
+            Alergeno::Pescado => "PESCADO",
+            Alergeno::Soya => "SOYA",
+            Alergeno::Vegetariano => "VEGETARIANO",
+            Alergeno::Vegano => "VEGANO",
+        }
+    }
+
+    /// Palabras (normalizadas, sin tildes) que nombran el alérgeno en un
+    /// comentario libre. Las de varias palabras deben aparecer seguidas.
+    /// Solo cuentan como alergia si van junto a una pista (`PISTAS`).
+    fn palabras_clave(&self) -> &'static [&'static str] {
+        match self {
+            Alergeno::Gluten => &["gluten"],
+            Alergeno::Mani => &["mani", "cacahuate", "cacahuete"],
+            Alergeno::FrutosSecos => {
+                &["frutos secos", "nuez", "nueces", "almendra", "almendras"]
+            }
+            Alergeno::Lactosa => &["lactosa", "lacteo", "lacteos"],
+            Alergeno::Huevo => &["huevo", "huevos"],
+            Alergeno::Mariscos => {
+                &["marisco", "mariscos", "camaron", "camarones", "crustaceos"]
+            }
+            Alergeno::Pescado => &["pescado", "pescados"],
+            Alergeno::Soya => &["soya", "soja"],
+            Alergeno::Vegetariano | Alergeno::Vegano => &[],
+        }
+    }
+
+    /// Palabras que por sí solas declaran la restricción ("celíaco",
+    /// "vegano"), sin necesidad de una pista.
+    fn declaraciones(&self) -> &'static [&'static str] {
+        match self {
+            Alergeno::Gluten => &["celiaco", "celiaca", "celiacos"],
+            Alergeno::Vegetariano => &["vegetariano", "vegetariana"],
+            Alergeno::Vegano => &["vegano", "vegana"],
+            _ => &[],
+        }
+    }
+}
+
+/// Palabras que, antes de un alérgeno, indican que el cliente lo quiere
+/// evitar ("sin", "no lleva", "alergia a").
+const PISTAS: [&str; 11] = [
+    "sin",
+    "no",
+    "alergia",
+    "alergias",
+    "alergico",
+    "alergica",
+    "alergicos",
+    "alergicas",
+    "intolerante",
+    "intolerantes",
+    "intolerancia",
+];
+
+/// Palabras que se saltan al buscar la pista ("alergia a los mariscos",
+/// "sin cebolla ni maní").
+const RELLENO: [&str; 14] = [
+    "a", "al", "el", "la", "lo", "los", "las", "de", "del", "ni", "y", "o",
+    "un", "una",
+];
+
+/// Cuántas palabras (sin contar relleno) antes del alérgeno se busca la
+/// pista: "sin cebolla ni maní" avisa, "sin cebolla, extra maní" no.
+const ALCANCE_PISTA: usize = 2;
+
+/// Pasa a minúsculas, quita tildes y separa en palabras.
+fn palabras_normalizadas(texto: &str) -> Vec<String> {
+    let normalizado: String = texto
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' => 'a',
+            'é' | 'è' | 'ë' => 'e',
+            'í' | 'ì' | 'ï' => 'i',
+            'ó' | 'ò' | 'ö' => 'o',
+            'ú' | 'ù' | 'ü' => 'u',
+            'ñ' => 'n',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    normalizado.split_whitespace().map(String::from).collect()
+}
+
+/// Posiciones donde aparece `clave` (de una o más palabras) en `palabras`.
+fn posiciones(palabras: &[String], clave: &str) -> Vec<usize> {
+    let clave: Vec<&str> = clave.split(' ').collect();
+    palabras
+        .windows(clave.len())
+        .enumerate()
+        .filter(|(_, ventana)| *ventana == clave)
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+/// Indica si alguna de las palabras que preceden a `pos` es una pista.
+fn tiene_pista(palabras: &[String], pos: usize) -> bool {
+    palabras[..pos]
+        .iter()
+        .rev()
+        .filter(|palabra| !RELLENO.contains(&palabra.as_str()))
+        .take(ALCANCE_PISTA)
+        .any(|palabra| PISTAS.contains(&palabra.as_str()))
+}
+
+/// Separa los alérgenos de un texto libre en alergias (con pista: "alergia
+/// al maní", "sin gluten", o declaradas: "celíaco") y simples menciones
+/// ("extra maní", "con huevo").
+fn clasificar(texto: &str) -> (Vec<Alergeno>, Vec<Alergeno>) {
+    let mut alergias = Vec::new();
+    let mut menciones = Vec::new();
+    // cada frase por separado, para que la pista no salte una coma
+    for frase in texto.split([',', '.', ';', ':', '!', '?', '\n']) {
+        let palabras = palabras_normalizadas(frase);
+        for alergeno in TODOS {
+            let declarado = alergeno
+                .declaraciones()
+                .iter()
+                .any(|clave| !posiciones(&palabras, clave).is_empty());
+            let (con_pista, sin_pista): (Vec<usize>, Vec<usize>) = alergeno
+                .palabras_clave()
+                .iter()
+                .flat_map(|clave| posiciones(&palabras, clave))
+                .partition(|&pos| tiene_pista(&palabras, pos));
+            if declarado || !con_pista.is_empty() {
+                alergias.push(alergeno);
+            } else if !sin_pista.is_empty() {
+                menciones.push(alergeno);
+            }
+        }
+    }
+    alergias.sort();
+    alergias.dedup();
+    menciones.retain(|alergeno| !alergias.contains(alergeno));
+    menciones.sort();
+    menciones.dedup();
+    (alergias, menciones)
+}
+
+/// Detecta las alergias indicadas en un texto libre ("alergia al maní",
+/// "sin gluten por favor"). Un alérgeno nombrado sin pista ("extra maní")
+/// no cuenta: ver `menciones_en_texto`.
+pub fn detectar_en_texto(texto: &str) -> Vec<Alergeno> {
+    clasificar(texto).0
+}
+
+/// Alérgenos que un texto libre nombra sin pedir evitarlos ("extra maní",
+/// "con huevo"). Se imprimen aparte, sin la banda de alergia.
+pub fn menciones_en_texto(texto: &str) -> Vec<Alergeno> {
+    clasificar(texto).1
+}
+
+/// Alérgenos de un ítem: los informados, los detectados en su comentario y
+/// los de sus componentes si es un combo.
+pub fn alergenos_item(item: &Item) -> Vec<Alergeno> {
+    let mut alergenos: Vec<Alergeno> =
+        item.alergenos.iter().flatten().copied().collect();
+    if let Some(comentario) = item.comentario.as_ref() {
+        alergenos.extend(detectar_en_texto(comentario));
+    }
+    for sub_item in item.sub_items.iter().flatten() {
+        alergenos.extend(alergenos_item(sub_item));
+    }
+    alergenos.sort();
+    alergenos.dedup();
+    alergenos
+}
+
+/// Todos los alérgenos de la orden, para la banda de advertencia del
+/// encabezado: los de la orden, los de su comentario y los de cada ítem.
+pub fn alergenos_orden(orden: &IOrder) -> Vec<Alergeno> {
+    let mut alergenos: Vec<Alergeno> =
+        orden.alergenos.iter().flatten().copied().collect();
+    if let Some(comentario) = orden.comentario.as_ref() {
+        alergenos.extend(detectar_en_texto(comentario));
+    }
+    for item in &orden.items {
+        alergenos.extend(alergenos_item(item));
+    }
+    alergenos.sort();
+    alergenos.dedup();
+    alergenos
+}
+
+/// Alérgenos que los comentarios del ítem (y de sus componentes) nombran
+/// sin ser alergias del ítem.
+pub fn menciones_item(item: &Item) -> Vec<Alergeno> {
+    let mut menciones: Vec<Alergeno> = item
+        .comentario
+        .iter()
+        .flat_map(|comentario| menciones_en_texto(comentario))
+        .collect();
+    for sub_item in item.sub_items.iter().flatten() {
+        menciones.extend(menciones_item(sub_item));
+    }
+    let alergenos = alergenos_item(item);
+    menciones.retain(|alergeno| !alergenos.contains(alergeno));
+    menciones.sort();
+    menciones.dedup();
+    menciones
+}
+
+/// Menciones de toda la orden, sin las que ya son alergias de la orden.
+pub fn menciones_orden(orden: &IOrder) -> Vec<Alergeno> {
+    let mut menciones: Vec<Alergeno> = orden
+        .comentario
+        .iter()
+        .flat_map(|comentario| menciones_en_texto(comentario))
+        .collect();
+    for item in &orden.items {
+        menciones.extend(menciones_item(item));
+    }
+    let alergenos = alergenos_orden(orden);
+    menciones.retain(|alergeno| !alergenos.contains(alergeno));
+    menciones.sort();
+    menciones.dedup();
+    menciones
+}
+
+/// Une las etiquetas para imprimirlas en una banda.
+pub fn etiquetas(alergenos: &[Alergeno]) -> String {
+    alergenos
+        .iter()
+        .map(Alergeno::etiqueta)
+        .collect::<Vec<&str>>()
+        .join(" / ")
+}
+
+/// Texto de la banda neutra de menciones, p.ej. "MENCIONA: MANÍ / HUEVO".
+pub fn etiqueta_menciones(menciones: &[Alergeno]) -> String {
+    String::from("MENCIONA: ")
+        + &menciones
+            .iter()
+            .map(Alergeno::nombre)
+            .collect::<Vec<&str>>()
+            .join(" / ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detecta_alergias_con_pista() {
+        assert_eq!(detectar_en_texto("Alergia al maní"), vec![Alergeno::Mani]);
+        assert_eq!(
+            detectar_en_texto("sin gluten por favor"),
+            vec![Alergeno::Gluten]
+        );
+        assert_eq!(detectar_en_texto("no lleva huevo"), vec![Alergeno::Huevo]);
+        assert_eq!(
+            detectar_en_texto("soy alérgico a los frutos secos"),
+            vec![Alergeno::FrutosSecos]
+        );
+        assert_eq!(
+            detectar_en_texto("sin cebolla ni maní"),
+            vec![Alergeno::Mani]
+        );
+        assert_eq!(detectar_en_texto("Soy celíaca"), vec![Alergeno::Gluten]);
+        assert_eq!(detectar_en_texto("vegano"), vec![Alergeno::Vegano]);
+    }
+
+    #[test]
+    fn menciones_sin_pista_no_son_alergias() {
+        for texto in ["extra maní", "con huevo", "sin cebolla, extra maní"] {
+            assert!(detectar_en_texto(texto).is_empty(), "{}", texto);
+        }
+        assert_eq!(menciones_en_texto("extra maní"), vec![Alergeno::Mani]);
+        assert_eq!(
+            menciones_en_texto("con huevo y camarones"),
+            vec![Alergeno::Huevo, Alergeno::Mariscos]
+        );
+        assert!(menciones_en_texto("sin aceitunas").is_empty());
+    }
+
+    #[test]
+    fn alergia_y_mencion_del_mismo_alergeno() {
+        // si en algún lado se pide evitarlo, manda la alergia
+        let texto = "extra maní en la salsa. Alergia al maní";
+        assert_eq!(detectar_en_texto(texto), vec![Alergeno::Mani]);
+        assert!(menciones_en_texto(texto).is_empty());
+    }
+
+    #[test]
+    fn etiqueta_de_menciones() {
+        assert_eq!(
+            etiqueta_menciones(&[Alergeno::Mani, Alergeno::Huevo]),
+            "MENCIONA: MANÍ / HUEVO"
+        );
+    }
+}
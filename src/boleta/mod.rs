@@ -0,0 +1,480 @@
+use std::fmt;
+
+use crate::impuestos::{calcular_desglose, DesgloseImpuestos, TASA_IVA};
+use crate::moneda::{a_unidad_minima, format_monto, Moneda};
+use crate::pdf417::{self, Pdf417, MAX_COLUMNAS};
+use crate::pdf_resources::{
+    Alineacion, AnchoColumna, Caja, Celda, Columna, EstiloTexto, OpcionesFila,
+    PdfResources, Tabla,
+};
+use crate::{IOrder, OpcionesTicket};
+
+/// Nivel de seguridad que exige el SII para el timbre electrónico.
+const NIVEL_ECC_TIMBRE: u8 = 5;
+/// El SII pide un timbre de al menos 2 x 5 cm y a lo más 4 x 9 cm; el ancho
+/// máximo queda limitado además por el área útil del papel de 80 mm.
+const TIMBRE_ANCHO_MM: f32 = 70.0;
+const TIMBRE_ALTO_MIN_MM: f32 = 20.0;
+const TIMBRE_ALTO_MAX_MM: f32 = 40.0;
+/// Alto de cada fila del PDF417 en módulos.
+const MODULOS_POR_FILA: f32 = 3.0;
+
+/// Datos de la boleta electrónica que no vienen en la orden.
+pub struct DatosBoleta {
+    /// Nodo `<TED>` firmado, tal como lo entrega el SII/facturador.
+    pub ted_xml: String,
+    pub razon_social: Option<String>,
+    pub giro: Option<String>,
+    pub direccion: Option<String>,
+    /// Unidad del SII, p.ej. "S.I.I. - SANTIAGO CENTRO".
+    pub unidad_sii: Option<String>,
+    pub resolucion_numero: i32,
+    pub resolucion_anio: i32,
+}
+
+/// Motivos por los que no se puede imprimir la boleta.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorBoleta {
+    /// Al TED le falta algún dato obligatorio del documento.
+    TedIncompleto,
+    /// El TED no cabe en un timbre PDF417 dentro de las medidas del SII.
+    TimbreMuyGrande,
+}
+
+impl fmt::Display for ErrorBoleta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorBoleta::TedIncompleto => {
+                write!(f, "TED sin datos del documento")
+            }
+            ErrorBoleta::TimbreMuyGrande => {
+                write!(f, "El TED no cabe en el timbre PDF417")
+            }
+        }
+    }
+}
+
+/// Boleta exenta electrónica; todo su monto va como exento.
+const TIPO_BOLETA_EXENTA: i32 = 41;
+
+/// Campos del `<DD>` del timbre que se imprimen en la boleta.
+pub struct Ted {
+    pub rut_emisor: String,
+    pub tipo_dte: i32,
+    pub folio: i64,
+    pub fecha_emision: String,
+    pub monto_total: i32,
+}
+
+/// Lee los datos del documento desde el XML del TED.
+///
+/// Devuelve `None` si falta alguno de los campos obligatorios.
+pub fn leer_ted(ted_xml: &str) -> Option<Ted> {
+    // el <CAF> también trae un <RE>, pero va después de los datos del DD
+    let dd = valor_tag(ted_xml, "DD")?;
+    Some(Ted {
+        rut_emisor: valor_tag(dd, "RE")?.trim().to_string(),
+        tipo_dte: valor_tag(dd, "TD")?.trim().parse().ok()?,
+        folio: valor_tag(dd, "F")?.trim().parse().ok()?,
+        fecha_emision: valor_tag(dd, "FE")?.trim().to_string(),
+        monto_total: valor_tag(dd, "MNT")?.trim().parse().ok()?,
+    })
+}
+
+/// Lee un archivo con el TED, aceptando UTF-8 o ISO-8859-1 (el encoding que
+/// usa el SII).
+pub fn leer_ted_archivo(ruta: &str) -> String {
+    let bytes = std::fs::read(ruta).expect("No se pudo leer el TED");
+    match String::from_utf8(bytes) {
+        Ok(texto) => texto,
+        Err(err) => err.into_bytes().iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Contenido de la primera aparición de `<tag ...>...</tag>`.
+fn valor_tag<'x>(xml: &'x str, tag: &str) -> Option<&'x str> {
+    let apertura = format!("<{}", tag);
+    let cierre = format!("</{}>", tag);
+    let mut desde = 0;
+    while let Some(pos) = xml[desde..].find(&apertura) {
+        let inicio_tag = desde + pos;
+        let resto = &xml[inicio_tag + apertura.len()..];
+        // descartamos tags que solo comparten prefijo (<F> vs <FE>)
+        if resto.starts_with('>') || resto.starts_with(' ') {
+            let inicio = inicio_tag + xml[inicio_tag..].find('>')? + 1;
+            let fin = inicio + xml[inicio..].find(&cierre)?;
+            return Some(&xml[inicio..fin]);
+        }
+        desde = inicio_tag + apertura.len();
+    }
+    None
+}
+
+/// Formatea un RUT como "76.123.456-7".
+pub fn format_rut(rut: &str) -> String {
+    let limpio: String = rut
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    if limpio.len() < 2 {
+        return limpio;
+    }
+    let (cuerpo, dv) = limpio.split_at(limpio.len() - 1);
+    let mut formateado = String::new();
+    for (count, ch) in cuerpo.chars().rev().enumerate() {
+        if count > 0 && count % 3 == 0 {
+            formateado.push('.');
+        }
+        formateado.push(ch);
+    }
+    formateado.chars().rev().collect::<String>() + "-" + dv
+}
+
+/// Convierte "AAAA-MM-DD" a "DD-MM-AAAA".
+fn format_fecha_emision(fecha: &str) -> String {
+    let partes: Vec<&str> = fecha.split('-').collect();
+    if partes.len() == 3 {
+        format!(
+            "{}-{}-{}",
+            partes[2], partes[1], partes[0]
+        )
+    } else {
+        fecha.to_string()
+    }
+}
+
+/// Codifica el TED y elige columnas y tamaño de módulo para que el timbre
+/// quede dentro de las medidas del SII.
+///
+/// Devuelve el símbolo, el ancho del módulo y el alto de fila en mm.
+pub fn dimensionar_timbre(ted_xml: &str) -> Option<(Pdf417, f32, f32)> {
+    // el SII firma y lee el TED en ISO-8859-1
+    let datos: Vec<u8> = ted_xml
+        .chars()
+        .map(|c| {
+            if (c as u32) < 256 {
+                c as u8
+            } else {
+                b'?'
+            }
+        })
+        .collect();
+
+    for columnas in 1..=MAX_COLUMNAS {
+        let Some(simbolo) =
+            pdf417::codificar(&datos, columnas, NIVEL_ECC_TIMBRE)
+        else {
+            continue;
+        };
+        let ancho_modulo = TIMBRE_ANCHO_MM / simbolo.ancho_modulos() as f32;
+        let mut alto_fila = ancho_modulo * MODULOS_POR_FILA;
+        let alto = simbolo.filas as f32 * alto_fila;
+        if alto > TIMBRE_ALTO_MAX_MM {
+            continue;
+        }
+        if alto < TIMBRE_ALTO_MIN_MM {
+            alto_fila = TIMBRE_ALTO_MIN_MM / simbolo.filas as f32;
+        }
+        return Some((simbolo, ancho_modulo, alto_fila));
+    }
+    None
+}
+
+/// Desglose de impuestos del monto timbrado.
+///
+/// El total de la boleta es el `<MNT>` del TED, así que neto, IVA y exento
+/// salen de ese monto: la orden solo aporta cuánto pesa cada categoría.
+fn desglose_timbrado(orden: &IOrder, ted: &Ted) -> DesgloseImpuestos {
+    if ted.tipo_dte == TIPO_BOLETA_EXENTA {
+        return DesgloseImpuestos::desde_montos(0, ted.monto_total, TASA_IVA);
+    }
+    calcular_desglose(orden, ted.monto_total as i64, TASA_IVA)
+}
+
+/// Genera la representación impresa de la boleta electrónica.
+pub fn pdf_boleta(
+    orden: &IOrder,
+    datos: &DatosBoleta,
+    opciones: &OpcionesTicket,
+) -> Result<(), ErrorBoleta> {
+    let ted = leer_ted(&datos.ted_xml).ok_or(ErrorBoleta::TedIncompleto)?;
+    let (timbre, ancho_modulo, alto_fila) =
+        dimensionar_timbre(&datos.ted_xml)
+            .ok_or(ErrorBoleta::TimbreMuyGrande)?;
+    let mut pdf = PdfResources::new();
+    pdf.depurar = opciones.depurar;
+    let mut y_actual = 0.;
+
+    // CUERPO 0: recuadro con RUT, tipo y folio
+    let inicio_rect = y_actual + 2.0;
+    let rut = String::from("R.U.T.: ") + &format_rut(&ted.rut_emisor);
+    y_actual = pdf.set_paragraph(
+        &rut,
+        14.0,
+        y_actual + 4.0,
+        Caja::centrada(70.0),
+        false,
+    );
+    let tipo_documento = if ted.tipo_dte == 41 {
+        String::from("BOLETA EXENTA ELECTRÓNICA")
+    } else {
+        String::from("BOLETA ELECTRÓNICA")
+    };
+    y_actual = pdf.set_paragraph(
+        &tipo_documento,
+        14.0,
+        y_actual + 1.0,
+        Caja::centrada(66.0),
+        false,
+    );
+    let folio = String::from("N° ") + &ted.folio.to_string();
+    y_actual = pdf.set_paragraph(
+        &folio,
+        14.0,
+        y_actual + 1.0,
+        Caja::centrada(70.0),
+        false,
+    );
+    y_actual += 2.0;
+    pdf.set_rect(inicio_rect, y_actual);
+    if let Some(unidad_sii) = datos.unidad_sii.as_ref() {
+        y_actual = pdf.set_paragraph(
+            unidad_sii,
+            11.0,
+            y_actual + 2.0,
+            Caja::centrada(70.0),
+            false,
+        );
+    }
+
+    // emisor
+    let razon_social = datos
+        .razon_social
+        .as_ref()
+        .or(orden.comercio.nombre.as_ref());
+    if let Some(razon_social) = razon_social {
+        y_actual = pdf.set_paragraph(
+            razon_social,
+            14.0,
+            y_actual + 5.0,
+            Caja::centrada(70.0),
+            false,
+        );
+    }
+    for dato in [&datos.giro, &datos.direccion].into_iter().flatten() {
+        y_actual = pdf.set_paragraph(
+            dato,
+            11.0,
+            y_actual + 1.0,
+            Caja::centrada(70.0),
+            true,
+        );
+    }
+    let fecha_emision = String::from("Fecha Emisión: ")
+        + &format_fecha_emision(&ted.fecha_emision);
+    y_actual = pdf.set_paragraph(
+        &fecha_emision,
+        12.0,
+        y_actual + 3.0,
+        Caja::izquierda(5.0, 70.0),
+        true,
+    );
+
+    // CUERPO 1: detalle
+    pdf.set_linea(y_actual + 1.0);
+    y_actual += 3.0;
+    let columna = |titulo: &str, ancho, alineacion| Columna {
+        titulo: titulo.to_string(),
+        ancho,
+        alineacion,
+    };
+    let detalle = Tabla {
+        columnas: vec![
+            columna("Cant.", AnchoColumna::Auto, Alineacion::Derecha),
+            columna(
+                "Detalle",
+                AnchoColumna::Fraccion(1.0),
+                Alineacion::Izquierda,
+            ),
+            columna("P. Unit.", AnchoColumna::Auto, Alineacion::Derecha),
+            columna("Total", AnchoColumna::Auto, Alineacion::Derecha),
+        ],
+        filas: orden
+            .items
+            .iter()
+            .map(|item| {
+                vec![
+                    item.cantidad.to_string(),
+                    item.nombre.clone(),
+                    format_monto(
+                        a_unidad_minima(item.precio, Moneda::Clp),
+                        Moneda::Clp,
+                    ),
+                    format_monto(
+                        a_unidad_minima(
+                            item.precio * item.cantidad,
+                            Moneda::Clp,
+                        ),
+                        Moneda::Clp,
+                    ),
+                ]
+            })
+            .collect(),
+        font_size: 11.0,
+        estilo_encabezado: EstiloTexto::Normal,
+        separadores: true,
+    };
+    y_actual = pdf.set_tabla(&detalle, y_actual + 2.0);
+
+    // FOOTER: totales
+    pdf.set_linea(y_actual + 1.0);
+    y_actual += 3.0;
+    let desglose = desglose_timbrado(orden, &ted);
+    let mut totales = vec![
+        ("Monto Neto".to_string(), desglose.neto),
+        (
+            format!("IVA ({}%)", TASA_IVA),
+            desglose.iva,
+        ),
+    ];
+    if desglose.exento > 0 {
+        totales.push((
+            "Monto Exento".to_string(),
+            desglose.exento,
+        ));
+    }
+    for (etiqueta, monto) in totales {
+        y_actual = pdf.set_row(
+            &Celda::light(&etiqueta, 12.0),
+            &Celda::light(&format_monto(monto as i64, Moneda::Clp), 12.0),
+            y_actual + 1.0,
+            &OpcionesFila::default(),
+        );
+    }
+    y_actual = pdf.set_row(
+        &Celda::bold("Total", 16.0),
+        &Celda::bold(
+            &format_monto(ted.monto_total as i64, Moneda::Clp),
+            16.0,
+        ),
+        y_actual + 2.0,
+        &OpcionesFila::default(),
+    );
+
+    // timbre electrónico
+    let ancho_timbre = timbre.ancho_modulos() as f32 * ancho_modulo;
+    y_actual = pdf.set_codigo_barras(
+        (80.0 - ancho_timbre) / 2.0,
+        y_actual + 4.0,
+        ancho_modulo,
+        alto_fila,
+        timbre.modulos,
+    );
+    let leyendas = [
+        String::from("Timbre Electrónico SII"),
+        format!(
+            "Res. N° {} de {}",
+            datos.resolucion_numero, datos.resolucion_anio
+        ),
+        String::from("Verifique documento: www.sii.cl"),
+    ];
+    y_actual += 3.0;
+    for leyenda in &leyendas {
+        y_actual = pdf.set_paragraph(
+            leyenda,
+            10.0,
+            y_actual + 1.0,
+            Caja::centrada(70.0),
+            true,
+        );
+    }
+
+    pdf.set_paragraph(
+        &String::from("powered by Agil"),
+        12.0,
+        y_actual + 4.0,
+        Caja::centrada(80.0),
+        true,
+    );
+
+    pdf.init_draw();
+    pdf.drow_all_obj();
+    pdf.save_pdf("boleta.pdf");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leer_ted_de_ejemplo() {
+        let ted = leer_ted(crate::TED_EJEMPLO).unwrap();
+        assert_eq!(ted.rut_emisor, "76123456-7");
+        assert_eq!(ted.tipo_dte, 39);
+        assert_eq!(ted.folio, 1234);
+        assert_eq!(ted.fecha_emision, "2024-12-25");
+        assert_eq!(ted.monto_total, 18500);
+    }
+
+    #[test]
+    fn leer_ted_no_confunde_f_con_fe() {
+        // <FE> antes que <F>: el folio no puede salir de la fecha
+        let ted = leer_ted(
+            "<TED><DD><RE>1-9</RE><TD>39</TD><FE>2024-01-02</FE>\
+             <F>77</F><MNT>100</MNT></DD></TED>",
+        )
+        .unwrap();
+        assert_eq!(ted.folio, 77);
+        assert_eq!(ted.fecha_emision, "2024-01-02");
+    }
+
+    #[test]
+    fn leer_ted_incompleto() {
+        assert!(leer_ted("<TED><DD><RE>1-9</RE></DD></TED>").is_none());
+        assert!(leer_ted("sin timbre").is_none());
+    }
+
+    #[test]
+    fn totales_cuadran_con_el_monto_timbrado() {
+        let orden = crate::crear_orden_ejemplo();
+        let ted = leer_ted(crate::TED_EJEMPLO).unwrap();
+        let desglose = desglose_timbrado(&orden, &ted);
+        assert_eq!(
+            desglose.neto + desglose.iva + desglose.exento,
+            ted.monto_total
+        );
+    }
+
+    #[test]
+    fn boleta_exenta_no_lleva_iva() {
+        let orden = crate::crear_orden_ejemplo();
+        let ted = Ted {
+            tipo_dte: TIPO_BOLETA_EXENTA,
+            ..leer_ted(crate::TED_EJEMPLO).unwrap()
+        };
+        let desglose = desglose_timbrado(&orden, &ted);
+        assert_eq!(desglose.iva, 0);
+        assert_eq!(desglose.exento, ted.monto_total);
+    }
+
+    #[test]
+    fn boleta_con_ted_incompleto_no_se_imprime() {
+        let datos = DatosBoleta {
+            ted_xml: "<TED><DD><RE>1-9</RE></DD></TED>".to_string(),
+            razon_social: None,
+            giro: None,
+            direccion: None,
+            unidad_sii: None,
+            resolucion_numero: 80,
+            resolucion_anio: 2014,
+        };
+        let resultado = pdf_boleta(
+            &crate::crear_orden_ejemplo(),
+            &datos,
+            &OpcionesTicket::default(),
+        );
+        assert_eq!(resultado, Err(ErrorBoleta::TedIncompleto));
+    }
+}
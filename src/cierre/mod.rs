@@ -0,0 +1,549 @@
+use std::fmt;
+
+use chrono::DateTime;
+
+use crate::moneda::{a_unidad_minima, format_monto, moneda_orden, Moneda};
+use crate::pdf_resources::{
+    format_datetime, Alineacion, AnchoColumna, Caja, Celda, Columna,
+    EstiloTexto, OpcionesFila, PdfResources, Tabla,
+};
+use crate::{medir_ticket, IOrder, OpcionesTicket};
+
+/// Cantidad de productos que se listan en el ranking del cierre.
+pub const TOP_ITEMS: usize = 5;
+
+/// Cantidad de órdenes y monto acumulado bajo un mismo concepto.
+pub struct Agrupado {
+    pub nombre: String,
+    pub cantidad: u32,
+    pub monto: i64,
+}
+
+/// Resumen de fin de turno de un conjunto de órdenes.
+///
+/// Los montos van en la unidad mínima de la moneda del comercio.
+pub struct ResumenCierre {
+    pub comercio: Option<String>,
+    pub moneda: Moneda,
+    pub ordenes: u32,
+    pub total: i64,
+    pub por_medio_pago: Vec<Agrupado>,
+    pub por_plataforma: Vec<Agrupado>,
+    pub por_tipo_entrega: Vec<Agrupado>,
+    /// Descuentos por tipo: cupón de despacho, cupón de subtotal y puntos.
+    pub descuentos: Vec<Agrupado>,
+    pub gastos_envio: i64,
+    /// Productos más vendidos con su cantidad, de mayor a menor.
+    pub top_items: Vec<(String, f32)>,
+    /// Fecha ISO de pago de la primera y la última orden.
+    pub primera_orden: Option<String>,
+    pub ultima_orden: Option<String>,
+    /// Papel usado por los tickets de las órdenes, en mm.
+    pub papel_mm: f32,
+}
+
+/// Las órdenes del turno vienen en más de una moneda y sus montos no se
+/// pueden sumar; lleva las monedas en el orden en que aparecen.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MonedasMezcladas(pub Vec<Moneda>);
+
+impl fmt::Display for MonedasMezcladas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let codigos: Vec<&str> = self.0.iter().map(Moneda::codigo).collect();
+        write!(f, "órdenes en más de una moneda ({})", codigos.join(", "))
+    }
+}
+
+/// Suma `monto` al concepto `nombre`, manteniendo el orden de aparición.
+fn acumular(grupos: &mut Vec<Agrupado>, nombre: &str, monto: i64) {
+    match grupos.iter_mut().find(|g| g.nombre == nombre) {
+        Some(grupo) => {
+            grupo.cantidad += 1;
+            grupo.monto += monto;
+        }
+        None => grupos.push(Agrupado {
+            nombre: nombre.to_string(),
+            cantidad: 1,
+            monto,
+        }),
+    }
+}
+
+impl ResumenCierre {
+    /// Agrega las órdenes del turno, que deben estar todas en la misma
+    /// moneda.
+    pub fn desde_ordenes(
+        ordenes: &[IOrder],
+    ) -> Result<Self, MonedasMezcladas> {
+        let mut monedas: Vec<Moneda> = Vec::new();
+        for moneda in ordenes.iter().map(moneda_orden) {
+            if !monedas.contains(&moneda) {
+                monedas.push(moneda);
+            }
+        }
+        if monedas.len() > 1 {
+            return Err(MonedasMezcladas(monedas));
+        }
+        let mut resumen = ResumenCierre {
+            comercio: ordenes.first().and_then(|o| o.comercio.nombre.clone()),
+            moneda: monedas.first().copied().unwrap_or_default(),
+            ordenes: ordenes.len() as u32,
+            total: 0,
+            por_medio_pago: Vec::new(),
+            por_plataforma: Vec::new(),
+            por_tipo_entrega: Vec::new(),
+            descuentos: Vec::new(),
+            gastos_envio: 0,
+            top_items: Vec::new(),
+            primera_orden: None,
+            ultima_orden: None,
+            papel_mm: 0.0,
+        };
+        let mut fechas_pago = Vec::new();
+
+        for orden in ordenes {
+            let total_orden: i64 = orden
+                .pago
+                .medios_pago
+                .iter()
+                .map(|medio| a_unidad_minima(medio.monto, resumen.moneda))
+                .sum();
+            resumen.total += total_orden;
+
+            for medio in &orden.pago.medios_pago {
+                let nombre = medio.nombre.as_deref().unwrap_or("Otro");
+                acumular(
+                    &mut resumen.por_medio_pago,
+                    nombre,
+                    a_unidad_minima(medio.monto, resumen.moneda),
+                );
+            }
+            let plataforma =
+                orden.plataforma.nombre.as_deref().unwrap_or("Otra");
+            acumular(
+                &mut resumen.por_plataforma,
+                plataforma,
+                total_orden,
+            );
+            acumular(
+                &mut resumen.por_tipo_entrega,
+                orden.tipo_entrega.nombre(),
+                total_orden,
+            );
+
+            let descuentos = [
+                (
+                    "Cupón despacho",
+                    orden.dscto_cupon_gasto_envio,
+                ),
+                (
+                    "Cupón subtotal",
+                    orden.dscto_cupon_subtotal,
+                ),
+                ("Puntos", orden.dscto_puntos),
+            ];
+            for (nombre, monto) in descuentos {
+                if monto != 0.0 {
+                    acumular(
+                        &mut resumen.descuentos,
+                        nombre,
+                        a_unidad_minima(monto.abs(), resumen.moneda),
+                    );
+                }
+            }
+            resumen.gastos_envio +=
+                a_unidad_minima(orden.gastos_envio, resumen.moneda);
+            resumen.papel_mm +=
+                medir_ticket(orden, &OpcionesTicket::default()).alto_mm;
+
+            for item in &orden.items {
+                match resumen
+                    .top_items
+                    .iter_mut()
+                    .find(|(n, _)| *n == item.nombre)
+                {
+                    Some((_, cantidad)) => *cantidad += item.cantidad,
+                    None => resumen
+                        .top_items
+                        .push((item.nombre.clone(), item.cantidad)),
+                }
+            }
+
+            let fecha_pago =
+                DateTime::parse_from_rfc3339(&orden.fechas.fecha_pago)
+                    .expect("Error parsing ISO date");
+            fechas_pago.push((
+                fecha_pago,
+                orden.fechas.fecha_pago.clone(),
+            ));
+        }
+
+        resumen.top_items.sort_by(|a, b| b.1.total_cmp(&a.1));
+        resumen.top_items.truncate(TOP_ITEMS);
+        fechas_pago.sort_by_key(|(fecha, _)| *fecha);
+        resumen.primera_orden = fechas_pago.first().map(|(_, iso)| iso.clone());
+        resumen.ultima_orden = fechas_pago.last().map(|(_, iso)| iso.clone());
+        Ok(resumen)
+    }
+
+    /// Exporta el resumen como CSV con columnas
+    /// `seccion,concepto,cantidad,monto`; los montos van sin formato, en la
+    /// unidad mínima de la moneda.
+    pub fn csv(&self) -> String {
+        let mut csv = String::from("seccion,concepto,cantidad,monto\n");
+        let mut fila =
+            |seccion: &str, concepto: &str, cantidad: String, monto: String| {
+                csv += &format!(
+                    "{},{},{},{}\n",
+                    seccion,
+                    campo_csv(concepto),
+                    cantidad,
+                    monto
+                );
+            };
+        fila(
+            "total",
+            "Ventas",
+            self.ordenes.to_string(),
+            self.total.to_string(),
+        );
+        let secciones = [
+            ("medio_pago", &self.por_medio_pago),
+            ("plataforma", &self.por_plataforma),
+            ("tipo_entrega", &self.por_tipo_entrega),
+            ("descuento", &self.descuentos),
+        ];
+        for (seccion, grupos) in secciones {
+            for grupo in grupos {
+                fila(
+                    seccion,
+                    &grupo.nombre,
+                    grupo.cantidad.to_string(),
+                    grupo.monto.to_string(),
+                );
+            }
+        }
+        fila(
+            "despacho",
+            "Gastos de envío",
+            String::new(),
+            self.gastos_envio.to_string(),
+        );
+        fila(
+            "papel",
+            "Tickets (mm)",
+            format!("{:.1}", self.papel_mm),
+            String::new(),
+        );
+        for (nombre, cantidad) in &self.top_items {
+            fila(
+                "top_items",
+                nombre,
+                cantidad.to_string(),
+                String::new(),
+            );
+        }
+        for (concepto, fecha) in [
+            ("Primera orden", &self.primera_orden),
+            ("Última orden", &self.ultima_orden),
+        ] {
+            if let Some(fecha) = fecha {
+                fila(
+                    "horario",
+                    concepto,
+                    String::new(),
+                    fecha.clone(),
+                );
+            }
+        }
+        csv
+    }
+}
+
+/// Encierra el campo entre comillas si trae separadores o comillas.
+fn campo_csv(campo: &str) -> String {
+    if campo.contains([',', '"', '\n']) {
+        format!("\"{}\"", campo.replace('"', "\"\""))
+    } else {
+        campo.to_string()
+    }
+}
+
+/// Imprime una fila con el concepto a la izquierda y el monto a la derecha,
+/// unidos con puntos guía si `guias`.
+fn set_fila(
+    pdf: &mut PdfResources,
+    concepto: &str,
+    monto: &str,
+    y_actual: f32,
+    guias: bool,
+) -> f32 {
+    pdf.set_row(
+        &Celda::light(concepto, 14.0),
+        &Celda::bold(monto, 14.0),
+        y_actual + 1.0,
+        &OpcionesFila {
+            guias,
+            ..OpcionesFila::default()
+        },
+    )
+}
+
+/// Imprime una sección con su título y una fila por concepto.
+fn set_seccion(
+    pdf: &mut PdfResources,
+    titulo: &str,
+    grupos: &[Agrupado],
+    moneda: Moneda,
+    mut y_actual: f32,
+) -> f32 {
+    if grupos.is_empty() {
+        return y_actual;
+    }
+    let tabla = Tabla {
+        columnas: vec![
+            Columna {
+                titulo: titulo.to_string(),
+                ancho: AnchoColumna::Fraccion(1.0),
+                alineacion: Alineacion::Izquierda,
+            },
+            Columna {
+                titulo: "Cant.".to_string(),
+                ancho: AnchoColumna::Fijo(12.0),
+                alineacion: Alineacion::Centro,
+            },
+            Columna {
+                titulo: "Monto".to_string(),
+                ancho: AnchoColumna::Auto,
+                alineacion: Alineacion::Derecha,
+            },
+        ],
+        filas: grupos
+            .iter()
+            .map(|grupo| {
+                vec![
+                    grupo.nombre.clone(),
+                    grupo.cantidad.to_string(),
+                    format_monto(grupo.monto, moneda),
+                ]
+            })
+            .collect(),
+        font_size: 13.0,
+        estilo_encabezado: EstiloTexto::Normal,
+        separadores: false,
+    };
+    y_actual = pdf.set_tabla(&tabla, y_actual + 6.0);
+    pdf.set_linea(y_actual + 1.0);
+    y_actual
+}
+
+/// Genera el ticket de cierre de caja con el resumen del turno.
+pub fn pdf_cierre(resumen: &ResumenCierre, opciones: &OpcionesTicket) {
+    let mut pdf = PdfResources::new();
+    pdf.depurar = opciones.depurar;
+    let moneda = resumen.moneda;
+    let mut y_actual = 0.;
+
+    // HEADER
+    y_actual = pdf.set_paragraph(
+        &String::from("CIERRE DE CAJA"),
+        24.0,
+        y_actual + 12.0,
+        Caja::centrada(70.0),
+        false,
+    );
+    if let Some(comercio) = resumen.comercio.as_ref() {
+        y_actual = pdf.set_paragraph(
+            comercio,
+            16.0,
+            y_actual + 1.5,
+            Caja::centrada(70.0),
+            false,
+        );
+    }
+    if let (Some(primera), Some(ultima)) = (
+        resumen.primera_orden.as_ref(),
+        resumen.ultima_orden.as_ref(),
+    ) {
+        let (dia_primera, hora_primera) = format_datetime(primera);
+        let (dia_ultima, hora_ultima) = format_datetime(ultima);
+        let horario = if dia_primera == dia_ultima {
+            format!(
+                "{} {} - {}",
+                dia_primera, hora_primera, hora_ultima
+            )
+        } else {
+            format!(
+                "{} {} - {} {}",
+                dia_primera, hora_primera, dia_ultima, hora_ultima
+            )
+        };
+        y_actual = pdf.set_paragraph(
+            &horario,
+            14.0,
+            y_actual,
+            Caja::centrada(70.0),
+            true,
+        );
+    }
+    pdf.set_separacion(y_actual + 2.0, "dinero");
+    y_actual += 8.0;
+
+    // CUERPO 1: totales
+    y_actual = set_fila(
+        &mut pdf,
+        &format!("Órdenes ({})", resumen.ordenes),
+        &format_monto(resumen.total, moneda),
+        y_actual,
+        false,
+    );
+    y_actual = set_fila(
+        &mut pdf,
+        "Despacho",
+        &format_monto(resumen.gastos_envio, moneda),
+        y_actual,
+        false,
+    );
+    y_actual = set_fila(
+        &mut pdf,
+        "Papel",
+        &format!("{:.2} m", resumen.papel_mm / 1000.0),
+        y_actual,
+        false,
+    );
+    pdf.set_linea(y_actual + 1.0);
+
+    // CUERPO 2: desglose
+    y_actual = set_seccion(
+        &mut pdf,
+        "Medios de pago",
+        &resumen.por_medio_pago,
+        moneda,
+        y_actual,
+    );
+    y_actual = set_seccion(
+        &mut pdf,
+        "Plataformas",
+        &resumen.por_plataforma,
+        moneda,
+        y_actual,
+    );
+    y_actual = set_seccion(
+        &mut pdf,
+        "Tipo de entrega",
+        &resumen.por_tipo_entrega,
+        moneda,
+        y_actual,
+    );
+    y_actual = set_seccion(
+        &mut pdf,
+        "Descuentos",
+        &resumen.descuentos,
+        moneda,
+        y_actual,
+    );
+
+    // CUERPO 3: productos más vendidos
+    if !resumen.top_items.is_empty() {
+        y_actual = pdf.set_paragraph_estilo(
+            &String::from("Más vendidos"),
+            16.0,
+            y_actual + 6.0,
+            Caja::izquierda(5.0, 70.0),
+            false,
+            EstiloTexto::Subrayado,
+        );
+        for (nombre, cantidad) in &resumen.top_items {
+            y_actual = set_fila(
+                &mut pdf,
+                nombre,
+                &cantidad.to_string(),
+                y_actual,
+                true,
+            );
+        }
+    }
+
+    pdf.set_paragraph(
+        &String::from("powered by Agil"),
+        12.0,
+        y_actual + 6.0,
+        Caja::centrada(80.0),
+        true,
+    );
+
+    pdf.init_draw();
+    pdf.drow_all_obj();
+    pdf.save_pdf("cierre.pdf");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MedioPago;
+
+    fn medio(nombre: Option<&str>, monto: f32) -> MedioPago {
+        MedioPago {
+            nombre: nombre.map(String::from),
+            monto,
+            efectivo: nombre == Some("Efectivo"),
+        }
+    }
+
+    fn orden(medios_pago: Vec<MedioPago>) -> IOrder {
+        let mut orden = crate::crear_orden_ejemplo();
+        orden.pago.medios_pago = medios_pago;
+        orden
+    }
+
+    fn turno() -> Vec<IOrder> {
+        vec![
+            orden(vec![
+                medio(Some("Tarjeta"), 2000.0),
+                medio(Some("Efectivo"), 3000.0),
+            ]),
+            orden(vec![medio(Some("Efectivo"), 1500.0), medio(None, 500.0)]),
+        ]
+    }
+
+    #[test]
+    fn desde_ordenes_suma_totales_y_medios_de_pago() {
+        let resumen = ResumenCierre::desde_ordenes(&turno()).unwrap();
+        assert_eq!(resumen.moneda, Moneda::Clp);
+        assert_eq!(resumen.ordenes, 2);
+        assert_eq!(resumen.total, 7000);
+        let medios: Vec<(&str, u32, i64)> = resumen
+            .por_medio_pago
+            .iter()
+            .map(|g| (g.nombre.as_str(), g.cantidad, g.monto))
+            .collect();
+        assert_eq!(
+            medios,
+            vec![("Tarjeta", 1, 2000), ("Efectivo", 2, 4500), ("Otro", 1, 500)]
+        );
+        assert_eq!(resumen.gastos_envio, 2000);
+    }
+
+    #[test]
+    fn desde_ordenes_rechaza_monedas_mezcladas() {
+        let mut ordenes = turno();
+        ordenes[1].moneda = Some("PEN".to_string());
+        let error = ResumenCierre::desde_ordenes(&ordenes).err().unwrap();
+        assert_eq!(error, MonedasMezcladas(vec![Moneda::Clp, Moneda::Pen]));
+        assert_eq!(
+            error.to_string(),
+            "órdenes en más de una moneda (CLP, PEN)"
+        );
+    }
+
+    #[test]
+    fn csv_con_totales_y_campos_escapados() {
+        let visa = medio(Some("Visa, \"débito\""), 1990.0);
+        let ordenes = vec![orden(vec![visa])];
+        let csv = ResumenCierre::desde_ordenes(&ordenes).unwrap().csv();
+        let lineas: Vec<&str> = csv.lines().collect();
+        assert_eq!(lineas[0], "seccion,concepto,cantidad,monto");
+        assert_eq!(lineas[1], "total,Ventas,1,1990");
+        assert_eq!(lineas[2], "medio_pago,\"Visa, \"\"débito\"\"\",1,1990");
+    }
+}
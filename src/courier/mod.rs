@@ -0,0 +1,87 @@
+/// Id de courier cuando el reparto lo hace el propio comercio.
+pub const ID_REPARTO_PROPIO: i32 = -2;
+
+/// Registro de couriers: una línea por courier con id, nombre y logo
+/// separados por tabulador. Las líneas con `#` son comentarios.
+pub const RUTA_COURIERS: &str = "assets/couriers.tsv";
+
+/// Empresa de reparto que retira las órdenes.
+#[derive(Debug, PartialEq)]
+pub struct DatosCourier {
+    pub id: i32,
+    pub nombre: String,
+    /// Nombre del logo en `assets/img` (sin extensión); ver `logo_opcional`.
+    pub logo: Option<String>,
+}
+
+/// Lee el registro de couriers; se saltan las líneas que no traen al menos
+/// un id numérico y un nombre.
+pub fn leer_couriers(texto: &str) -> Vec<DatosCourier> {
+    texto
+        .lines()
+        .filter(|linea| !linea.trim_start().starts_with('#'))
+        .filter_map(|linea| {
+            let mut campos = linea.split('\t').map(str::trim);
+            let id = campos.next()?.parse().ok()?;
+            let nombre = campos.next().filter(|nombre| !nombre.is_empty())?;
+            let logo = campos.next().filter(|logo| !logo.is_empty());
+            Some(DatosCourier {
+                id,
+                nombre: nombre.to_string(),
+                logo: logo.map(String::from),
+            })
+        })
+        .collect()
+}
+
+/// Busca el courier por su id: el reparto propio siempre existe y el resto
+/// sale de `RUTA_COURIERS`. Sin registro no se reconoce ningún otro.
+pub fn datos_courier(id_courier: i32) -> Option<DatosCourier> {
+    if id_courier == ID_REPARTO_PROPIO {
+        return Some(DatosCourier {
+            id: ID_REPARTO_PROPIO,
+            nombre: "Reparto propio".to_string(),
+            logo: None,
+        });
+    }
+    let registro = std::fs::read_to_string(RUTA_COURIERS).unwrap_or_default();
+    leer_couriers(&registro)
+        .into_iter()
+        .find(|courier| courier.id == id_courier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leer_couriers_del_registro() {
+        let registro = "# id\tnombre\tlogo\n\
+                        1\tRappi\tcouriers/rappi\n\
+                        7\tMensajería Sur\n\
+                        x\tSin id\n\
+                        8\t\n";
+        assert_eq!(
+            leer_couriers(registro),
+            vec![
+                DatosCourier {
+                    id: 1,
+                    nombre: "Rappi".to_string(),
+                    logo: Some("couriers/rappi".to_string()),
+                },
+                DatosCourier {
+                    id: 7,
+                    nombre: "Mensajería Sur".to_string(),
+                    logo: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reparto_propio_no_depende_del_registro() {
+        let courier = datos_courier(ID_REPARTO_PROPIO).unwrap();
+        assert_eq!(courier.nombre, "Reparto propio");
+        assert_eq!(courier.logo, None);
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Ruta por defecto del historial de impresiones.
+pub const RUTA_HISTORIAL: &str = "historial_impresiones.tsv";
+
+/// Resultado de registrar una impresión.
+pub struct RegistroImpresion {
+    /// Número de impresión de la orden (1 = primera impresión).
+    pub intento: u32,
+    /// Fecha ISO de la primera impresión, si esta es una reimpresión.
+    pub fecha_original: Option<String>,
+}
+
+/// Historial de impresiones por `codigo` de orden, guardado en un archivo de
+/// texto con una línea `codigo<TAB>fecha_iso` por impresión.
+pub struct HistorialImpresiones {
+    ruta: PathBuf,
+}
+
+impl HistorialImpresiones {
+    pub fn new(ruta: &str) -> Self {
+        HistorialImpresiones {
+            ruta: PathBuf::from(ruta),
+        }
+    }
+
+    /// Fechas de las impresiones previas de la orden, de la más antigua a la
+    /// más reciente.
+    pub fn impresiones(&self, codigo: &str) -> io::Result<Vec<String>> {
+        let archivo = match File::open(&self.ruta) {
+            Ok(archivo) => archivo,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(err),
+        };
+        let mut fechas = Vec::new();
+        for linea in BufReader::new(archivo).lines() {
+            let linea = linea?;
+            if let Some((codigo_linea, fecha)) = linea.split_once('\t') {
+                if codigo_linea == codigo {
+                    fechas.push(fecha.to_string());
+                }
+            }
+        }
+        Ok(fechas)
+    }
+
+    /// Registra una nueva impresión de la orden en `fecha` y devuelve el
+    /// número de intento junto a la fecha de la impresión original.
+    pub fn registrar(
+        &self,
+        codigo: &str,
+        fecha: &str,
+    ) -> io::Result<RegistroImpresion> {
+        let previas = self.impresiones(codigo)?;
+        let mut archivo = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.ruta)?;
+        writeln!(archivo, "{}\t{}", codigo, fecha)?;
+
+        Ok(RegistroImpresion {
+            intento: previas.len() as u32 + 1,
+            fecha_original: previas.into_iter().next(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Historial en un archivo temporal propio de cada test, que se borra
+    /// al terminar.
+    struct HistorialTemporal(HistorialImpresiones);
+
+    impl HistorialTemporal {
+        fn new(nombre: &str) -> Self {
+            let ruta = std::env::temp_dir().join(format!(
+                "historial_{}_{}.tsv",
+                nombre,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&ruta);
+            HistorialTemporal(HistorialImpresiones { ruta })
+        }
+    }
+
+    impl Drop for HistorialTemporal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0.ruta);
+        }
+    }
+
+    #[test]
+    fn sin_archivo_no_hay_impresiones() {
+        let historial = HistorialTemporal::new("vacio");
+        assert!(historial.0.impresiones("P1").unwrap().is_empty());
+        assert!(!historial.0.ruta.exists());
+    }
+
+    #[test]
+    fn registrar_cuenta_reimpresiones_por_orden() {
+        let historial = HistorialTemporal::new("reimpresiones");
+        let historial = &historial.0;
+
+        let primera =
+            historial.registrar("P1", "2024-12-26T10:00:00Z").unwrap();
+        assert_eq!(primera.intento, 1);
+        assert_eq!(primera.fecha_original, None);
+
+        historial.registrar("P2", "2024-12-26T10:05:00Z").unwrap();
+        let segunda =
+            historial.registrar("P1", "2024-12-26T10:10:00Z").unwrap();
+        assert_eq!(segunda.intento, 2);
+        assert_eq!(
+            segunda.fecha_original.as_deref(),
+            Some("2024-12-26T10:00:00Z")
+        );
+
+        assert_eq!(
+            historial.impresiones("P1").unwrap(),
+            vec!["2024-12-26T10:00:00Z", "2024-12-26T10:10:00Z"]
+        );
+        assert_eq!(historial.impresiones("P2").unwrap().len(), 1);
+        assert!(historial.impresiones("P3").unwrap().is_empty());
+    }
+}
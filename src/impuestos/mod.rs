@@ -0,0 +1,226 @@
+use crate::IOrder;
+use crate::moneda::{a_unidad_minima, format_monto, moneda_orden};
+use crate::pdf_resources::{Celda, PdfResources};
+
+/// Tasa general de IVA en Chile, en porcentaje.
+pub const TASA_IVA: u32 = 19;
+
+/// Categoría tributaria de un ítem.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CategoriaImpuesto {
+    /// Precio con IVA incluido (la mayoría de los productos).
+    #[default]
+    Afecto,
+    /// Exento de IVA (p.ej. agua embotellada en promoción).
+    Exento,
+}
+
+/// Configuración de la sección de impuestos del footer.
+pub struct ConfigImpuestos {
+    /// Si es `false` no se imprime la sección.
+    pub mostrar: bool,
+    /// Tasa de IVA en porcentaje (19 por defecto).
+    pub tasa_iva: u32,
+    /// Imprime la línea "Exento" aunque su monto sea 0.
+    pub mostrar_exento_en_cero: bool,
+}
+
+impl Default for ConfigImpuestos {
+    fn default() -> Self {
+        ConfigImpuestos {
+            mostrar: true,
+            tasa_iva: TASA_IVA,
+            mostrar_exento_en_cero: false,
+        }
+    }
+}
+
+/// Desglose de impuestos de una orden, en la unidad mínima de la moneda.
+///
+/// `neto + iva + exento` siempre suma el total desglosado.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DesgloseImpuestos {
+    pub neto: i32,
+    pub iva: i32,
+    pub exento: i32,
+}
+
+impl DesgloseImpuestos {
+    /// Separa un monto bruto afecto (IVA incluido) en neto e IVA.
+    ///
+    /// El neto se redondea al peso más cercano y el IVA se obtiene por
+    /// diferencia, para que la suma siempre cuadre con el bruto.
+    pub fn desde_montos(afecto_bruto: i32, exento: i32, tasa_iva: u32) -> Self {
+        let bruto = afecto_bruto.max(0) as i64;
+        let divisor = 100 + tasa_iva as i64;
+        let neto = (bruto * 200 + divisor) / (2 * divisor);
+        DesgloseImpuestos {
+            neto: neto as i32,
+            iva: (bruto - neto) as i32,
+            exento: exento.max(0),
+        }
+    }
+}
+
+/// Calcula el desglose de impuestos de `total`, el monto que se cobra por la
+/// orden en la unidad mínima de su moneda.
+///
+/// El total se prorratea entre afecto y exento según lo que pesa cada
+/// categoría en los ítems; el despacho pesa como afecto. Así los
+/// descuentos quedan repartidos y `neto + iva + exento` cuadra con el total
+/// impreso.
+pub fn calcular_desglose(
+    orden: &IOrder,
+    total: i64,
+    tasa_iva: u32,
+) -> DesgloseImpuestos {
+    let moneda = moneda_orden(orden);
+    let mut afecto = a_unidad_minima(orden.gastos_envio, moneda);
+    let mut exento = 0;
+    for item in &orden.items {
+        let monto = a_unidad_minima(item.precio * item.cantidad, moneda);
+        match item.impuesto {
+            CategoriaImpuesto::Afecto => afecto += monto,
+            CategoriaImpuesto::Exento => exento += monto,
+        }
+    }
+
+    let total = total.max(0);
+    let base = afecto + exento;
+    let total_exento = if base > 0 {
+        ((total * exento * 2 + base) / (2 * base)).clamp(0, total)
+    } else {
+        0
+    };
+
+    DesgloseImpuestos::desde_montos(
+        (total - total_exento) as i32,
+        total_exento as i32,
+        tasa_iva,
+    )
+}
+
+/// Agrega la sección de impuestos del `total` de la orden al PDF y devuelve
+/// el nuevo `y`.
+pub fn set_seccion_impuestos(
+    pdf: &mut PdfResources,
+    orden: &IOrder,
+    total: i64,
+    config: &ConfigImpuestos,
+    y_inicial: f32,
+) -> f32 {
+    if !config.mostrar {
+        return y_inicial;
+    }
+    let desglose = calcular_desglose(orden, total, config.tasa_iva);
+    let moneda = moneda_orden(orden);
+
+    let mut filas: Vec<(String, i32)> = vec![
+        ("Neto".to_string(), desglose.neto),
+        (format!("IVA ({}%)", config.tasa_iva), desglose.iva),
+    ];
+    if desglose.exento > 0 || config.mostrar_exento_en_cero {
+        filas.push(("Exento".to_string(), desglose.exento));
+    }
+
+    let mut flujo = pdf.flujo(y_inicial);
+    flujo.separacion(1.0);
+    for (etiqueta, monto) in filas {
+        flujo.fila(
+            Celda::light(&etiqueta, 12.0),
+            Celda::light(&format_monto(monto as i64, moneda), 12.0),
+        );
+    }
+    flujo.y()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desde_montos_redondea_el_neto() {
+        assert_eq!(
+            DesgloseImpuestos::desde_montos(11900, 0, TASA_IVA),
+            DesgloseImpuestos {
+                neto: 10000,
+                iva: 1900,
+                exento: 0,
+            }
+        );
+        // 1000 / 1,19 = 840,34 y 990 / 1,19 = 831,93
+        assert_eq!(DesgloseImpuestos::desde_montos(1000, 0, 19).neto, 840);
+        assert_eq!(DesgloseImpuestos::desde_montos(990, 0, 19).neto, 832);
+    }
+
+    #[test]
+    fn desde_montos_cuadra_con_el_bruto() {
+        for bruto in [1, 7, 99, 1190, 4999, 123457] {
+            let desglose = DesgloseImpuestos::desde_montos(bruto, 250, 19);
+            assert_eq!(desglose.neto + desglose.iva, bruto);
+            assert_eq!(desglose.exento, 250);
+        }
+    }
+
+    #[test]
+    fn desde_montos_sin_negativos() {
+        assert_eq!(
+            DesgloseImpuestos::desde_montos(-500, -3, TASA_IVA),
+            DesgloseImpuestos::default()
+        );
+    }
+
+    fn orden_con_items(afecto: f32, exento: f32, envio: f32) -> IOrder {
+        let mut orden = crate::crear_orden_ejemplo();
+        orden.items.truncate(1);
+        orden.items[0].cantidad = 1.0;
+        orden.items[0].precio = afecto;
+        orden.items[0].impuesto = CategoriaImpuesto::Afecto;
+        let mut item_exento = orden.items[0].clone();
+        item_exento.precio = exento;
+        item_exento.impuesto = CategoriaImpuesto::Exento;
+        orden.items.push(item_exento);
+        orden.gastos_envio = envio;
+        orden
+    }
+
+    #[test]
+    fn calcular_desglose_cuadra_con_el_total() {
+        let orden = crate::crear_orden_ejemplo();
+        for total in [0, 1, 6000, 18500, 32490] {
+            let desglose = calcular_desglose(&orden, total, TASA_IVA);
+            assert_eq!(
+                (desglose.neto + desglose.iva + desglose.exento) as i64,
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn calcular_desglose_prorratea_por_categoria() {
+        // mitad afecto y mitad exento, con un descuento de 1000 en el total
+        let orden = orden_con_items(2000.0, 2000.0, 0.0);
+        assert_eq!(
+            calcular_desglose(&orden, 3000, TASA_IVA),
+            DesgloseImpuestos::desde_montos(1500, 1500, TASA_IVA)
+        );
+    }
+
+    #[test]
+    fn calcular_desglose_despacho_es_afecto() {
+        // 3000 de ítems exentos y 1000 de despacho
+        let orden = orden_con_items(0.0, 3000.0, 1000.0);
+        let desglose = calcular_desglose(&orden, 4000, TASA_IVA);
+        assert_eq!(desglose.exento, 3000);
+        assert_eq!(desglose.neto + desglose.iva, 1000);
+    }
+
+    #[test]
+    fn calcular_desglose_sin_items_es_afecto() {
+        let orden = orden_con_items(0.0, 0.0, 0.0);
+        assert_eq!(
+            calcular_desglose(&orden, 1190, TASA_IVA),
+            DesgloseImpuestos::desde_montos(1190, 0, TASA_IVA)
+        );
+    }
+}
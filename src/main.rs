@@ -2,11 +2,41 @@ extern crate printpdf;
 
 use std::{convert::From};
 
+mod alergenos;
+mod boleta;
+mod cierre;
+mod courier;
+mod historial;
+mod impuestos;
+mod modificacion;
+mod moneda;
+mod pdf417;
 mod pdf_resources;
+mod plataforma;
+mod programado;
+use alergenos::{
+    alergenos_item, alergenos_orden, etiqueta_menciones, etiquetas,
+    menciones_item, menciones_orden, Alergeno,
+};
+use boleta::{leer_ted_archivo, pdf_boleta, DatosBoleta};
+use cierre::{pdf_cierre, ResumenCierre};
+use courier::{datos_courier, ID_REPARTO_PROPIO};
+use historial::{HistorialImpresiones, RUTA_HISTORIAL};
+use impuestos::{set_seccion_impuestos, CategoriaImpuesto, ConfigImpuestos};
+use modificacion::{pdf_modificacion, TipoModificacion};
+use moneda::{a_unidad_minima, format_monto, moneda_orden, Moneda};
 use pdf_resources::{
-    format_clp, format_datetime, PdfResources,
+    format_datetime, logo_opcional, AjusteTexto, Alineacion, Caja, Celda,
+    EstiloCaja, EstiloLinea, EstiloTexto, MedidaTicket, OpcionesFila,
+    PdfResources,
+};
+use plataforma::marca_plataforma;
+use programado::{
+    es_programado, etiqueta_dia, ventana_entrega, UMBRAL_PROGRAMADO_MIN,
 };
 
+/// Orden tal como llega de la plataforma. Los montos vienen en unidades de
+/// la moneda de la orden (ver `moneda::a_unidad_minima`).
 pub struct IOrder {
     pub comentario: Option<String>,
     pub items: Vec<Item>,
@@ -25,20 +55,42 @@ pub struct IOrder {
     pub codigo: String,
     pub comercio: Comercio,
     pub pago: Pago,
-    pub sub_total: i32,
-    pub gastos_envio: i32,
+    pub sub_total: f32,
+    pub gastos_envio: f32,
+    /// Código ISO de la moneda; si no viene se usa la del comercio.
+    pub moneda: Option<String>,
+    /// Flag explícito de entrega programada; si no viene se deduce de las
+    /// fechas (ver `programado::es_programado`).
+    pub entrega_programada: Option<bool>,
+    /// Alergias o restricciones informadas para toda la orden.
+    pub alergenos: Option<Vec<Alergeno>>,
+    /// Mesa, mesero y cubiertos de una orden para consumo en local.
+    pub mesa: Option<Mesa>,
 }
 
 /// Representa un producto o ítem dentro de la orden.
+///
+/// Un combo ("Promo Familiar") trae sus componentes en `sub_items`; el precio
+/// del combo va solo en el ítem de primer nivel.
+#[derive(Clone)]
 pub struct Item {
     pub cantidad: f32, // Equivalente a `number` en TS
     pub nombre: String,
     pub precio: f32,
+    /// Precio unitario antes de la oferta; si es mayor que `precio` se
+    /// imprime tachado junto al precio.
+    pub precio_lista: Option<f32>,
     pub opciones: Option<Vec<IOpciones>>,
     pub comentario: Option<String>,
+    pub impuesto: CategoriaImpuesto,
+    /// Componentes del combo, con cantidades por unidad del combo.
+    pub sub_items: Option<Vec<Item>>,
+    /// Alergias o restricciones informadas para este ítem.
+    pub alergenos: Option<Vec<Alergeno>>,
 }
 
 /// Representa una opción o modificador de un ítem (equivalente a IOpciones).
+#[derive(Clone)]
 pub struct IOpciones {
     pub modificador: String,
     pub cantidad: i32,
@@ -54,13 +106,67 @@ pub struct Cupon {
 pub struct Fechas {
     pub fecha_salida_cocina_estimada: String,
     pub fecha_entrega_min: String,
+    /// Fin de la ventana de entrega, si la orden tiene una.
+    pub fecha_entrega_max: Option<String>,
     pub tz: String,
     pub fecha_pago: String,
 }
 
-/// Tipo de entrega (por ejemplo, 1 = Delivery, 2 = Retiro en local).
-pub struct TipoEntrega {
-    pub id: i32,
+/// Tipo de entrega de la orden (ids 1 = Delivery, 2 = Retiro en local,
+/// 3 = Consumo en local, 4 = Drive-thru).
+///
+/// Un id desconocido se imprime como Delivery, que es el ticket con más
+/// datos: `TipoEntrega::try_from(id).unwrap_or_default()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TipoEntrega {
+    #[default]
+    Delivery,
+    Retiro,
+    ConsumoEnLocal,
+    DriveThru,
+}
+
+impl TipoEntrega {
+    /// Ícono del encabezado del ticket.
+    pub fn icono(&self) -> &'static str {
+        match self {
+            TipoEntrega::Delivery => "moto",
+            TipoEntrega::Retiro => "camino",
+            TipoEntrega::ConsumoEnLocal => "cubiertos",
+            TipoEntrega::DriveThru => "auto",
+        }
+    }
+
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            TipoEntrega::Delivery => "Delivery",
+            TipoEntrega::Retiro => "Retiro en local",
+            TipoEntrega::ConsumoEnLocal => "Consumo en local",
+            TipoEntrega::DriveThru => "Drive-thru",
+        }
+    }
+}
+
+impl TryFrom<i32> for TipoEntrega {
+    /// El id desconocido.
+    type Error = i32;
+
+    fn try_from(id: i32) -> Result<Self, Self::Error> {
+        match id {
+            1 => Ok(TipoEntrega::Delivery),
+            2 => Ok(TipoEntrega::Retiro),
+            3 => Ok(TipoEntrega::ConsumoEnLocal),
+            4 => Ok(TipoEntrega::DriveThru),
+            _ => Err(id),
+        }
+    }
+}
+
+/// Datos de la mesa cuando la orden es para consumo en local.
+pub struct Mesa {
+    pub numero: String,
+    pub mesero: Option<String>,
+    pub cubiertos: Option<i32>,
 }
 
 /// Información opcional cuando es envío a domicilio.
@@ -72,6 +178,16 @@ pub struct DropOff {
 /// Información sobre el courier (por ejemplo, IdCourier = -2 cuando es propio).
 pub struct Courier {
     pub id_courier: i32,
+    /// Repartidor asignado, si ya se conoce.
+    pub repartidor: Option<Repartidor>,
+    /// Hora estimada (ISO) en que el repartidor retira la orden.
+    pub eta_retiro: Option<String>,
+}
+
+/// Repartidor que retira la orden en el local.
+pub struct Repartidor {
+    pub nombre: Option<String>,
+    pub telefono: Option<String>,
 }
 
 /// Datos del cliente.
@@ -90,326 +206,673 @@ pub struct Sucursal {
 pub struct Plataforma {
     pub codigo: Option<String>,
     pub nombre: Option<String>,
+    /// Id de la orden en la plataforma, con el que la buscan los repartidores.
+    pub id_orden: Option<String>,
 }
 
 /// Datos del comercio o restaurante que recibe la orden.
 pub struct Comercio {
     pub nombre: Option<String>,
+    /// Código ISO de la moneda en que opera el comercio (CLP por defecto).
+    pub moneda: Option<String>,
 }
 
-/// Información del pago (medio de pago, etc.).
+/// Información del pago: uno o más medios de pago y, si se paga en
+/// efectivo, con cuánto paga el cliente.
 pub struct Pago {
-    pub medio_pago: MedioPago,
+    pub medios_pago: Vec<MedioPago>,
+    /// Monto en efectivo con el que paga el cliente (para calcular vuelto).
+    pub paga_con: Option<f32>,
 }
 
-/// Medio de pago específico (efectivo, tarjeta, etc.).
+/// Medio de pago específico (efectivo, tarjeta, etc.) y el monto pagado
+/// con él.
 pub struct MedioPago {
     pub nombre: Option<String>,
+    pub monto: f32,
+    pub efectivo: bool,
 }
 
-fn pdf(orden: &IOrder) {
-    let mut pdf = PdfResources::new();
-    let mut y_actual = 0.;
-    // CUERPO 0: header
+impl Pago {
+    /// Suma de los montos pagados en efectivo.
+    pub fn total_efectivo(&self) -> f32 {
+        self.medios_pago
+            .iter()
+            .filter(|medio| medio.efectivo)
+            .map(|medio| medio.monto)
+            .sum()
+    }
 
-    // reimpreso:
-    let reimpreso = String::from("*REIMPRESO*");
-    pdf.set_paragraph(&reimpreso, 16.0, 5.0, 70.0, -1, false);
+    /// Vuelto a entregar al cliente, si indicó con cuánto paga, en la unidad
+    /// mínima de `moneda`.
+    pub fn vuelto(&self, moneda: Moneda) -> Option<Vuelto> {
+        let efectivo = a_unidad_minima(self.total_efectivo(), moneda);
+        self.paga_con.map(|paga_con| {
+            let diferencia = a_unidad_minima(paga_con, moneda) - efectivo;
+            if diferencia < 0 {
+                Vuelto::Falta(-diferencia)
+            } else {
+                Vuelto::Entregar(diferencia)
+            }
+        })
+    }
+}
 
-    // comercio nombre
-    let comercio_nombre = orden.comercio.nombre.as_ref().unwrap();
-    y_actual = pdf.set_paragraph(
-        &comercio_nombre,
-        20.0,
-        y_actual + 12.0,
-        70.0,
-        0,
-        false,
-    );
+/// Vuelto del pago en efectivo, en la unidad mínima de la moneda.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Vuelto {
+    /// Monto a entregar al cliente (0 si paga justo).
+    Entregar(i64),
+    /// El cliente indicó un monto menor al efectivo a cobrar: falta esto.
+    Falta(i64),
+}
 
-    // plataforma nombre
-    let plataforma_nombre = orden.plataforma.nombre.as_ref().unwrap();
-    y_actual = pdf.set_paragraph(
-        &plataforma_nombre,
-        16.0,
-        y_actual + 0.0,
-        70.0,
-        0,
-        false,
-    );
-    // moto
-    if orden.tipo_entrega.id == 1 {
-        pdf.set_img(5.0, y_actual + 4.0, 10.0, 10.0, "moto");
+/// Opciones de impresión del ticket que no vienen en la orden.
+pub struct OpcionesTicket {
+    pub impuestos: ConfigImpuestos,
+    /// Minutos entre pago y entrega para considerar la orden programada.
+    pub umbral_programado_min: i64,
+    /// Número de impresión de la orden (1 = primera impresión).
+    pub intento_impresion: u32,
+    /// Fecha ISO de la primera impresión, para las reimpresiones.
+    pub fecha_impresion_original: Option<String>,
+    /// Fecha ISO de esta impresión, contra la que se calcula el "Hoy" o
+    /// "Mañana" de las órdenes programadas (por defecto, ahora).
+    pub fecha_impresion: String,
+    /// Ticket de cocina: lista los componentes de los combos en vez del
+    /// combo, omite los precios de los ítems y no lleva la sección de pagos.
+    pub explotar_combos: bool,
+    /// Dibuja encima del ticket las guías del layout, para ajustar offsets.
+    pub depurar: bool,
+}
+
+impl Default for OpcionesTicket {
+    fn default() -> Self {
+        OpcionesTicket {
+            impuestos: ConfigImpuestos::default(),
+            umbral_programado_min: UMBRAL_PROGRAMADO_MIN,
+            intento_impresion: 1,
+            fecha_impresion_original: None,
+            fecha_impresion: chrono::Local::now().to_rfc3339(),
+            explotar_combos: false,
+            depurar: false,
+        }
+    }
+}
+
+/// Agrega un ítem de la orden (nombre, precio, modificadores y comentario)
+/// y devuelve el nuevo `y`.
+fn set_item(
+    pdf: &mut PdfResources,
+    item: &Item,
+    moneda: Moneda,
+    y_inicial: f32,
+    estilo: EstiloTexto,
+) -> f32 {
+    set_item_nivel(pdf, item, Some(moneda), y_inicial, estilo, 0)
+}
+
+/// Agrega un ítem con sangría según su `nivel` dentro del combo y luego sus
+/// componentes un nivel más adentro.
+///
+/// Si `moneda` es `None` no se imprime el precio (componentes de combos y
+/// tickets de cocina).
+fn set_item_nivel(
+    pdf: &mut PdfResources,
+    item: &Item,
+    moneda: Option<Moneda>,
+    y_inicial: f32,
+    estilo: EstiloTexto,
+    nivel: i8,
+) -> f32 {
+    let sangria = 5.0 * nivel as f32;
+    let (espacio, tamano, light) = if nivel == 0 {
+        (5.0, 13.0, false)
     } else {
-        pdf.set_img(
-            5.0,
-            y_actual + 4.0,
-            10.0,
-            10.0,
-            "camino",
+        (1.0, 12.0, true)
+    };
+    // sin moneda (ticket de cocina) se omite el precio
+    let nombre = item.cantidad.to_string() + " X " + &item.nombre;
+    let precio = match moneda {
+        Some(moneda) => {
+            let num_precio =
+                a_unidad_minima(item.precio * item.cantidad, moneda);
+            format_monto(num_precio, moneda)
+        }
+        None => String::new(),
+    };
+    let mut flujo = pdf.flujo(y_inicial);
+    flujo.espacio(espacio).fila_con(
+        Celda {
+            texto: &nombre,
+            font_size: tamano,
+            light,
+            estilo,
+        },
+        Celda {
+            texto: &precio,
+            font_size: tamano,
+            light,
+            estilo,
+        },
+        &OpcionesFila {
+            sangria,
+            ..OpcionesFila::default()
+        },
+    );
+    // precio antes de la oferta, tachado bajo el precio
+    if let (Some(moneda), Some(precio_lista)) = (moneda, item.precio_lista) {
+        if precio_lista > item.precio {
+            let num_lista =
+                a_unidad_minima(precio_lista * item.cantidad, moneda);
+            flujo.espacio(0.5).parrafo_estilo(
+                &format_monto(num_lista, moneda),
+                11.0,
+                Caja::derecha(5.0, 70.0),
+                true,
+                EstiloTexto::Tachado,
+            );
+        }
+    }
+    // alérgenos junto al ítem (no en los ítems eliminados)
+    let alergenos = alergenos_item(item);
+    if !alergenos.is_empty() && estilo != EstiloTexto::Tachado {
+        flujo.espacio(2.0).parrafo_estilo(
+            &etiquetas(&alergenos),
+            11.0,
+            Caja::izquierda(10.0 + sangria, 60.0 - sangria),
+            false,
+            EstiloTexto::Invertido,
         );
     }
-
-    // nuestro (si es reparto propio)
-    if orden.courier.id_courier == -2 {
-        let nuestro_string = String::from("*NUESTRO*");
-        let nuestro: &String = &nuestro_string;
-        pdf.set_paragraph(
-            &nuestro,
-            16.0,
-            y_actual + 10.0,
-            70.0,
-            -1,
+    // alérgenos que el comentario nombra sin pedir evitarlos
+    let menciones = menciones_item(item);
+    if !menciones.is_empty() && estilo != EstiloTexto::Tachado {
+        flujo.espacio(2.0).parrafo(
+            &etiqueta_menciones(&menciones),
+            11.0,
+            Caja::izquierda(10.0 + sangria, 60.0 - sangria),
             false,
         );
     }
-    let entrega_programada = true;
-    // correlativo
+    let caja_opcion = Caja::izquierda(10.0 + sangria, 70.0 - sangria);
+    for modi in item.opciones.iter().flatten() {
+        flujo
+            .espacio(2.0)
+            .parrafo_estilo(
+                &("- ".to_string() + &modi.modificador),
+                13.0,
+                caja_opcion,
+                true,
+                estilo,
+            )
+            .parrafo_estilo(
+                &(modi.cantidad.to_string() + " X   " + &modi.opcion),
+                13.0,
+                caja_opcion,
+                true,
+                estilo,
+            );
+    }
+    let comentario = item.comentario.as_deref().unwrap_or("");
+    if !comentario.is_empty() {
+        let ped_inicio_rect = flujo.y() + 1.0;
+        // comentario cliente, entre comillas dobles
+        let ped_comentario: String = "\"".to_string() + comentario + "\"";
+        flujo
+            .espacio(2.0)
+            .parrafo_estilo(
+                " Comentario del Cliente: ",
+                14.0,
+                Caja::izquierda(10.0, 60.0),
+                false,
+                estilo,
+            )
+            .espacio(1.0)
+            .parrafo_estilo(
+                &ped_comentario,
+                16.0,
+                Caja::justificada(5.0, 70.0).con_relleno(3.0),
+                true,
+                estilo,
+            )
+            .espacio(2.0)
+            .caja(ped_inicio_rect, EstiloCaja::sombreada());
+    }
+    // componentes del combo, sin precio
+    for sub_item in item.sub_items.iter().flatten() {
+        flujo.bloque(|pdf, y| {
+            set_item_nivel(pdf, sub_item, None, y, estilo, nivel + 1)
+        });
+    }
+    flujo.y()
+}
+
+/// Reemplaza cada combo por sus componentes, multiplicando las cantidades
+/// por las del combo, para que cocina vea lo que tiene que preparar.
+///
+/// Los componentes no llevan precio. El comentario y las opciones del combo
+/// pasan al primer componente y sus alérgenos a todos los componentes.
+pub fn explotar_combos(items: &[Item]) -> Vec<Item> {
+    let mut explotados = Vec::new();
+    for item in items {
+        let Some(sub_items) = item.sub_items.as_ref() else {
+            explotados.push(item.clone());
+            continue;
+        };
+        let mut componentes = explotar_combos(sub_items);
+        for componente in componentes.iter_mut() {
+            componente.cantidad *= item.cantidad;
+            componente.precio = 0.0;
+            if let Some(alergenos) = item.alergenos.as_ref() {
+                let propios = componente.alergenos.get_or_insert_with(Vec::new);
+                propios.extend(alergenos);
+                propios.sort();
+                propios.dedup();
+            }
+        }
+        if let (Some(primero), Some(opciones)) =
+            (componentes.first_mut(), item.opciones.as_ref())
+        {
+            if !opciones.is_empty() {
+                primero
+                    .opciones
+                    .get_or_insert_with(Vec::new)
+                    .extend(opciones.iter().cloned());
+            }
+        }
+        if let (Some(primero), Some(comentario)) =
+            (componentes.first_mut(), item.comentario.as_ref())
+        {
+            if !comentario.is_empty() {
+                let propio = primero.comentario.take().unwrap_or_default();
+                primero.comentario = Some(if propio.is_empty() {
+                    comentario.clone()
+                } else {
+                    propio + ". " + comentario
+                });
+            }
+        }
+        explotados.extend(componentes);
+    }
+    explotados
+}
+
+/// Tamaños de los textos del encabezado que varían de largo: los cortos se
+/// agrandan para leerse de lejos y los largos se achican hasta cortarse.
+const AJUSTE_COMERCIO: AjusteTexto = AjusteTexto {
+    max_lineas: 2,
+    font_min: 16.0,
+    font_max: 24.0,
+};
+const AJUSTE_CLIENTE: AjusteTexto = AjusteTexto {
+    max_lineas: 2,
+    font_min: 18.0,
+    font_max: 32.0,
+};
+/// El correlativo va en una línea, al lado de *NUESTRO*.
+const AJUSTE_CORRELATIVO: AjusteTexto = AjusteTexto {
+    max_lineas: 1,
+    font_min: 32.0,
+    font_max: 50.0,
+};
+const ANCHO_CORRELATIVO: f32 = 40.0;
+
+/// Arma el layout del ticket de la orden, sin crear el documento PDF.
+fn layout_ticket(
+    orden: &IOrder,
+    opciones: &OpcionesTicket,
+) -> PdfResources<'static> {
+    let mut pdf = PdfResources::new();
+    pdf.depurar = opciones.depurar;
+    let moneda = moneda_orden(orden);
+    let entrega_programada =
+        es_programado(orden, opciones.umbral_programado_min);
     // si es programado entonces se antepone el "P" al codigo
     let mut correlativo_string = orden.correlativo.to_string();
     if entrega_programada {
         correlativo_string.insert(0, 'P');
-        // prgramado
-        let programado_string = String::from("PROGRAMADO");
-        pdf.set_paragraph(
-            &programado_string,
-            16.0,
-            y_actual + 18.0,
-            70.0,
-            -1,
+    }
+    let tamano_correlativo = pdf.tamano_ajustado(
+        &correlativo_string,
+        &AJUSTE_CORRELATIVO,
+        ANCHO_CORRELATIVO,
+        false,
+    );
+
+    // CUERPO 0: header
+    let mut flujo = pdf.flujo(0.0);
+    flujo.seccion("encabezado");
+
+    // reimpreso: solo desde la segunda impresión
+    if opciones.intento_impresion > 1 {
+        let mut reimpreso = String::from("Reimpresión N° ")
+            + &opciones.intento_impresion.to_string();
+        if let Some(original) = opciones.fecha_impresion_original.as_ref() {
+            reimpreso += &(String::from(" — original ")
+                + &format_datetime(original).1);
+        }
+        flujo.espacio(5.0).parrafo(
+            &reimpreso,
+            12.0,
+            Caja::centrada(70.0),
             false,
         );
     }
 
-    // codigo pedido
-    let codigo_pedido = String::from("#") + &orden.codigo;
-    pdf.set_paragraph(
-        &codigo_pedido,
-        16.0,
-        y_actual + 18.0,
-        70.0,
-        1,
-        true,
-    );
-    y_actual = pdf.set_paragraph(
-        &correlativo_string,
-        50.0,
-        y_actual + 10.0,
-        70.0,
-        1,
+    // banda de alergias, bajo la de reimpresión si la hay
+    let alergenos = alergenos_orden(orden);
+    if !alergenos.is_empty() {
+        flujo.espacio(5.0).parrafo_estilo(
+            &etiquetas(&alergenos),
+            14.0,
+            Caja::centrada(66.0),
+            false,
+            EstiloTexto::Invertido,
+        );
+    }
+    // y una banda neutra si solo se nombran, p.ej. "extra maní"
+    let menciones = menciones_orden(orden);
+    if !menciones.is_empty() {
+        flujo.espacio(3.0).parrafo(
+            &etiqueta_menciones(&menciones),
+            12.0,
+            Caja::centrada(66.0),
+            false,
+        );
+    }
+
+    // comercio nombre
+    let comercio_nombre = orden.comercio.nombre.as_ref().unwrap();
+    flujo.espacio(12.0).parrafo_ajustado(
+        comercio_nombre,
+        &AJUSTE_COMERCIO,
+        Caja::centrada(70.0),
         false,
     );
 
+    // plataforma: logo y nombre según su marca, o el nombre que venga
+    flujo.espacio(1.5);
+    match marca_plataforma(&orden.plataforma) {
+        Some(marca) => {
+            // con logo el nombre se centra en lo que queda a su izquierda
+            let mut caja_nombre = Caja::centrada(70.0);
+            if let Some(logo) = logo_opcional(marca.logo) {
+                flujo.imagen(65.0, 10.0, 10.0, logo);
+                caja_nombre = Caja {
+                    alineacion: Alineacion::Centro,
+                    ..Caja::izquierda(5.0, 58.0)
+                };
+            }
+            let espacio_banda = match marca.estilo {
+                EstiloTexto::Invertido => 2.0,
+                _ => 0.0,
+            };
+            flujo
+                .espacio(espacio_banda)
+                .parrafo_estilo(
+                    marca.nombre,
+                    16.0,
+                    caja_nombre,
+                    false,
+                    marca.estilo,
+                )
+                .espacio(espacio_banda);
+            if let (Some(etiqueta), Some(id_orden)) =
+                (marca.etiqueta_id, orden.plataforma.id_orden.as_ref())
+            {
+                flujo.parrafo(
+                    &(String::from(etiqueta) + ": " + id_orden),
+                    14.0,
+                    Caja::centrada(70.0),
+                    true,
+                );
+            }
+        }
+        None => {
+            let plataforma_nombre = orden.plataforma.nombre.as_ref().unwrap();
+            flujo.parrafo(
+                plataforma_nombre,
+                16.0,
+                Caja::centrada(70.0),
+                false,
+            );
+        }
+    }
 
+    // correlativo, a la derecha y en la misma línea base que *NUESTRO* (si
+    // es reparto propio), con el icono del tipo de entrega encima
+    let nuestro = if orden.courier.id_courier == ID_REPARTO_PROPIO {
+        "*NUESTRO*"
+    } else {
+        ""
+    };
+    flujo
+        .espacio(0.5)
+        .imagen(5.0, 10.0, 10.0, orden.tipo_entrega.icono())
+        .espacio(2.0)
+        .fila_con(
+            Celda::bold(nuestro, 16.0).con_estilo(EstiloTexto::Invertido),
+            Celda::bold(&correlativo_string, tamano_correlativo),
+            &OpcionesFila {
+                ancho_derecha: Some(ANCHO_CORRELATIVO),
+                ..OpcionesFila::default()
+            },
+        );
 
-    // hora salida cociona
-    let salida_cocina =
-        format_datetime(&orden.fechas.fecha_salida_cocina_estimada.as_ref());
-    pdf.set_paragraph(
-        &salida_cocina.1,
-        32.0,
-        y_actual,
-        70.0,
-        1,
-        false,
+    // programado y codigo pedido
+    let programado = if entrega_programada { "PROGRAMADO" } else { "" };
+    let codigo_pedido = String::from("#") + &orden.codigo;
+    flujo.fila(
+        Celda::bold(programado, 16.0).con_estilo(EstiloTexto::Invertido),
+        Celda::light(&codigo_pedido, 16.0),
     );
 
-    // salida cocina
-    let salida_cocina_string = String::from("Salida Cocina");
-    y_actual = pdf.set_paragraph(
-        &salida_cocina_string,
-        12.0,
-        y_actual,
-        70.0,
-        -1,
-        true,
+    // dia y ventana de entrega de la orden programada
+    if entrega_programada {
+        let dia_entrega = etiqueta_dia(
+            &opciones.fecha_impresion,
+            &orden.fechas.fecha_entrega_min,
+            &orden.fechas.tz,
+        );
+        let programado_para =
+            dia_entrega + " " + &ventana_entrega(&orden.fechas);
+        flujo
+            .espacio(4.0)
+            .parrafo(&programado_para, 20.0, Caja::centrada(70.0), false)
+            .espacio(4.0);
+    }
+
+    // mesa, mesero y cubiertos de consumo en local
+    if let Some(mesa) = orden.mesa.as_ref() {
+        let mesa_string = String::from("MESA ") + &mesa.numero;
+        flujo.espacio(2.0).parrafo(
+            &mesa_string,
+            32.0,
+            Caja::centrada(70.0),
+            false,
+        );
+        let mut detalle_mesa: Vec<String> = Vec::new();
+        if let Some(mesero) = mesa.mesero.as_ref() {
+            detalle_mesa.push(String::from("Mesero: ") + mesero);
+        }
+        if let Some(cubiertos) = mesa.cubiertos {
+            detalle_mesa.push(cubiertos.to_string() + " cubiertos");
+        }
+        if !detalle_mesa.is_empty() {
+            flujo.espacio(1.0).parrafo(
+                &detalle_mesa.join(" · "),
+                16.0,
+                Caja::centrada(70.0),
+                false,
+            );
+        }
+        flujo.espacio(4.0);
+    }
+
+    // quién retira la orden y cuándo
+    let courier = datos_courier(orden.courier.id_courier);
+    let repartidor = orden.courier.repartidor.as_ref();
+    let eta_retiro = orden.courier.eta_retiro.as_ref();
+    if courier.is_some() || repartidor.is_some() || eta_retiro.is_some() {
+        if let Some(courier) = courier {
+            flujo.espacio(6.0);
+            // sin logo el nombre ocupa todo el ancho
+            let mut caja_retira = Caja::izquierda(5.0, 70.0);
+            if let Some(logo) = logo_opcional(courier.logo.as_deref()) {
+                flujo.imagen(65.0, 10.0, 10.0, logo);
+                caja_retira = Caja::izquierda(5.0, 55.0);
+            }
+            let retira = String::from("Retira: ") + &courier.nombre;
+            flujo.parrafo(&retira, 16.0, caja_retira, false);
+        }
+        if let Some(repartidor) = repartidor {
+            let datos_repartidor: Vec<&str> = [
+                repartidor.nombre.as_deref(),
+                repartidor.telefono.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if !datos_repartidor.is_empty() {
+                flujo.espacio(1.0).parrafo(
+                    &datos_repartidor.join(" · "),
+                    14.0,
+                    Caja::izquierda(5.0, 70.0),
+                    true,
+                );
+            }
+        }
+        if let Some(eta_retiro) = eta_retiro {
+            flujo.espacio(6.0).fila(
+                Celda::light("Retiro estimado", 12.0),
+                Celda::bold(&format_datetime(eta_retiro).1, 20.0),
+            );
+        }
+        flujo.espacio(1.0);
+    }
+
+    // salida cocina y su hora
+    let salida_cocina =
+        format_datetime(orden.fechas.fecha_salida_cocina_estimada.as_ref());
+    flujo.fila(
+        Celda::light("Salida Cocina", 12.0),
+        Celda::bold(&salida_cocina.1, 32.0),
     );
-    y_actual += 5.0;
     // Cliente nombre
-    pdf.set_linea(y_actual - 4.0);
     let cliente_nombre = orden.cliente.nombre.as_ref().unwrap();
-    y_actual = pdf.set_paragraph(
-        &cliente_nombre,
-        24.0,
-        y_actual + 4.0,
-        70.0,
-        0,
-        false,
-    );
+    flujo
+        .seccion("cliente")
+        .espacio(1.0)
+        .linea(EstiloLinea::segmentada(0.35))
+        .espacio(2.0)
+        .parrafo_ajustado(
+            cliente_nombre,
+            &AJUSTE_CLIENTE,
+            Caja::centrada(70.0),
+            false,
+        );
     // ubicacion
-    pdf.set_separacion(y_actual - 4.0, "ubicacion");
-    // si es delivery
-    y_actual += 4.0;
-    let direccion = if orden.tipo_entrega.id == 1 {
-        orden.drop_off.as_ref().unwrap().direccion.as_ref().unwrap() as &String
-    } else {
-        orden.sucursal.as_ref().unwrap().nombre.as_ref().unwrap() as &String
+    flujo
+        .seccion("entrega")
+        .espacio(2.0)
+        .separador("ubicacion")
+        .espacio(4.0);
+    // en delivery va la dirección del cliente, en el resto la sucursal
+    let direccion = match orden.tipo_entrega {
+        TipoEntrega::Delivery => {
+            orden.drop_off.as_ref().and_then(|d| d.direccion.as_ref())
+        }
+        _ => orden.sucursal.as_ref().and_then(|s| s.nombre.as_ref()),
     };
-    y_actual = pdf.set_paragraph(
-        &direccion,
-        14.0,
-        y_actual + 5.0,
-        50.0,
-        0,
-        false,
-    );
-    let tipo_entrega = orden
-        .drop_off
-        .as_ref()
-        .unwrap()
-        .tipo_entrega
-        .as_ref()
-        .unwrap();
-    y_actual = pdf.set_paragraph(
+    if let Some(direccion) = direccion {
+        flujo.espacio(5.0).parrafo(
+            direccion,
+            14.0,
+            Caja::centrada(50.0),
+            false,
+        );
+    }
+    let tipo_entrega = match (orden.tipo_entrega, orden.drop_off.as_ref()) {
+        (TipoEntrega::Delivery, Some(drop_off)) => drop_off
+            .tipo_entrega
+            .clone()
+            .unwrap_or_else(|| orden.tipo_entrega.nombre().to_string()),
+        _ => orden.tipo_entrega.nombre().to_string(),
+    };
+    flujo.espacio(3.0).parrafo(
         &tipo_entrega,
         14.0,
-        y_actual + 3.0,
-        80.0,
-        0,
+        Caja::centrada(80.0),
         true,
     );
 
-    // hora pago
-    let static_hora_pago = String::from("Hora de Pago");
-    pdf.set_paragraph(
-        &static_hora_pago,
-        14.0,
-        y_actual + 2.0,
-        70.0,
-        -1,
-        true,
-    );
-    let fecha_pago = format_datetime(&orden.fechas.fecha_pago.as_ref());
-    let str_fecha_entrega = fecha_pago.0 + ". - " + &fecha_pago.1;
-    y_actual = pdf.set_paragraph(
-        &str_fecha_entrega,
-        14.0,
-        y_actual + 2.0,
-        70.0,
-        1,
-        true,
-    );
-    // hora entrega
-    let static_hora_entrega = String::from("Hora de Entrega");
-    pdf.set_paragraph(
-        &static_hora_entrega,
-        14.0,
-        y_actual + 1.0,
-        70.0,
-        -1,
-        true,
-    );
+    // hora pago y hora entrega
+    let fecha_pago = format_datetime(orden.fechas.fecha_pago.as_ref());
+    let str_fecha_pago = fecha_pago.0 + ". - " + &fecha_pago.1;
     let fecha_entrega =
-        format_datetime(&orden.fechas.fecha_entrega_min.as_ref());
+        format_datetime(orden.fechas.fecha_entrega_min.as_ref());
     let str_fecha_entrega = fecha_entrega.0 + ". - " + &fecha_entrega.1;
-    y_actual = pdf.set_paragraph(
-        &str_fecha_entrega,
-        14.0,
-        y_actual + 1.0,
-        70.0,
-        1,
-        true,
+    flujo.espacio(2.0).fila(
+        Celda::light("Hora de Pago", 14.0),
+        Celda::light(&str_fecha_pago, 14.0),
     );
-    let inicio_rect = y_actual - 3.0;
-    // comentario cliente
-    let static_comentario_cliente = String::from(" Comentario del Cliente: ");
-    y_actual = pdf.set_paragraph(
-        &static_comentario_cliente,
-        14.0,
-        y_actual + 2.0,
-        60.0,
-        -2,
-        false,
+    flujo.espacio(1.0).fila(
+        Celda::light("Hora de Entrega", 14.0),
+        Celda::light(&str_fecha_entrega, 14.0),
     );
-    // agregar comillas dobles al final e inicio del texto
+    // comentario cliente, entre comillas dobles
+    let inicio_rect = flujo.y() + 1.0;
     let comentario: String =
-        " \"".to_string() + orden.comentario.as_ref().unwrap() + "\"";
-
-    y_actual = pdf.set_paragraph(
-        &comentario,
-        16.0,
-        y_actual + 1.0,
-        68.0,
-        0,
-        true,
-    );
-    pdf.set_rect(inicio_rect, y_actual - 2.0);
-    y_actual += 2.0;
-    pdf.set_separacion(y_actual, "cubiertos");
-    let mut precio_total = 0;
-    y_actual += 5.0;
-    // CUERPO 2: pedidos
-    for item in &orden.items {
-        pdf.set_paragraph(
-            &(item.cantidad.to_string() + " X " + &item.nombre),
-            13.0,
-            y_actual + 5.0,
-            70.0,
-            -1,
+        "\"".to_string() + orden.comentario.as_ref().unwrap() + "\"";
+    flujo
+        .espacio(2.0)
+        .parrafo(
+            " Comentario del Cliente: ",
+            14.0,
+            Caja::izquierda(10.0, 60.0),
             false,
-        );
-        let num_precio = (item.precio * item.cantidad) as i32;
-        precio_total += num_precio;
-        let precio = format_clp(num_precio);
-        y_actual = pdf.set_paragraph(
-            &precio,
-            13.0,
-            y_actual + 5.0,
-            70.0,
-            1,
-            false,
-        );
-        for modi in item.opciones.as_ref().unwrap() {
-            y_actual = pdf.set_paragraph(
-                &("- ".to_string() + &modi.modificador),
-                13.0,
-                y_actual + 2.0,
-                70.0,
-                -2,
-                true,
-            );
-            y_actual = pdf.set_paragraph(
-                &("".to_string()
-                    + &modi.cantidad.to_string()
-                    + " X   "
-                    + &modi.opcion),
-                13.0,
-                y_actual + 0.0,
-                70.0,
-                -2,
-                true,
-            );
+        )
+        .espacio(1.0)
+        .parrafo(
+            &comentario,
+            16.0,
+            Caja::justificada(5.0, 70.0).con_relleno(3.0),
+            true,
+        )
+        .espacio(2.0)
+        .caja(inicio_rect, EstiloCaja::sombreada())
+        .espacio(2.0)
+        .separador("cubiertos")
+        .espacio(1.5);
+
+    // CUERPO 2: pedidos
+    flujo.seccion("pedidos");
+    if opciones.explotar_combos {
+        for item in &explotar_combos(&orden.items) {
+            flujo.bloque(|pdf, y| {
+                set_item_nivel(pdf, item, None, y, EstiloTexto::Normal, 0)
+            });
         }
-        if item.comentario.as_ref().unwrap() != "" {
-            let ped_inicio_rect = y_actual - 3.0;
-            // comentario cliente
-            let static_comentario_cliente =
-                String::from(" Comentario del Cliente: ");
-            y_actual = pdf.set_paragraph(
-                &static_comentario_cliente,
-                14.0,
-                y_actual + 2.0,
-                60.0,
-                -2,
-                false,
-            );
-            // agregar comillas dobles al final e inicio del texto
-            let ped_comentario: String =
-                " \"".to_string() + item.comentario.as_ref().unwrap() + "\"";
-            y_actual = pdf.set_paragraph(
-                &ped_comentario,
-                16.0,
-                y_actual + 1.0,
-                68.0,
-                0,
-                true,
-            );
-            pdf.set_rect(ped_inicio_rect, y_actual - 2.0);
+    } else {
+        for item in &orden.items {
+            flujo.bloque(|pdf, y| {
+                set_item(pdf, item, moneda, y, EstiloTexto::Normal)
+            });
         }
     }
+    // cocina no cobra: su ticket termina en los pedidos
+    if opciones.explotar_combos {
+        return pdf;
+    }
+    let precio_total: i64 = orden
+        .items
+        .iter()
+        .map(|item| a_unidad_minima(item.precio * item.cantidad, moneda))
+        .sum();
 
     // FOOTER: pagos
-    pdf.set_separacion(y_actual, "dinero");
+    flujo.seccion("pagos").espacio(4.0).separador("dinero").espacio(4.0);
 
     let descuento_monto: (f32, bool, String) = // bool es si es cupon de gasto envio o no
         if orden.dscto_cupon_gasto_envio > 0.0 {
@@ -434,168 +897,181 @@ fn pdf(orden: &IOrder) {
             )
         };
 
-    let descuento_oferta = orden.sub_total - precio_total;
-    let gastos_envio = orden.gastos_envio;
-    let total = orden.sub_total + orden.gastos_envio + descuento_monto.0 as i32;
-
-    y_actual += 8.0;
-    pdf.set_paragraph(
-        &String::from("Subtotal"),
-        16.0,
-        y_actual + 2.0,
-        70.0,
-        -1,
-        true,
-    );
-    let precio_subtotal = format_clp(orden.sub_total);
-    y_actual = pdf.set_paragraph(
-        &precio_subtotal,
-        16.0,
-        y_actual + 2.0,
-        70.0,
-        1,
-        false,
-    );
+    let sub_total = a_unidad_minima(orden.sub_total, moneda);
+    let gastos_envio = a_unidad_minima(orden.gastos_envio, moneda);
+    let descuento = a_unidad_minima(descuento_monto.0, moneda);
+    let descuento_oferta = sub_total - precio_total;
+    let total = sub_total + gastos_envio - descuento;
 
+    let precio_subtotal = format_monto(sub_total, moneda);
+    flujo.separacion(1.0);
+    flujo.espacio(1.0).fila(
+        Celda::light("Subtotal", 16.0),
+        Celda::bold(&precio_subtotal, 16.0),
+    );
     if descuento_oferta > 0 {
-        pdf.set_paragraph(
-            &String::from("Descuento Oferta"),
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            -1,
-            true,
-        );
-        let precio_descuento_oferta = format_clp(descuento_oferta);
-        y_actual = pdf.set_paragraph(
-            &precio_descuento_oferta,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            1,
-            false,
+        let precio_descuento_oferta = format_monto(descuento_oferta, moneda);
+        flujo.fila(
+            Celda::light("Descuento Oferta", 16.0),
+            Celda::bold(&precio_descuento_oferta, 16.0),
         );
     }
+    let precio_descuento_monto = format_monto(descuento, moneda);
     if descuento_monto.0 > 0.0 && !descuento_monto.1 {
-        pdf.set_paragraph(
-            &descuento_monto.2,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            -1,
-            true,
-        );
-        let precio_descuento_monto = format_clp(descuento_monto.0 as i32);
-        y_actual = pdf.set_paragraph(
-            &precio_descuento_monto,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            1,
-            false,
+        flujo.fila(
+            Celda::light(&descuento_monto.2, 16.0),
+            Celda::bold(&precio_descuento_monto, 16.0),
         );
     }
     if gastos_envio > 0 {
-        pdf.set_paragraph(
-            &String::from("Despacho"),
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            -1,
-            true,
-        );
-        let precio_gastos_envio = format_clp(gastos_envio);
-        y_actual = pdf.set_paragraph(
-            &precio_gastos_envio,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            1,
-            false,
+        let precio_gastos_envio = format_monto(gastos_envio, moneda);
+        flujo.fila(
+            Celda::light("Despacho", 16.0),
+            Celda::bold(&precio_gastos_envio, 16.0),
         );
     }
     if descuento_monto.0 > 0.0 && descuento_monto.1 {
-        pdf.set_paragraph(
-            &descuento_monto.2,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            -1,
-            true,
-        );
-        let precio_descuento_monto = format_clp(descuento_monto.0 as i32);
-        y_actual = pdf.set_paragraph(
-            &precio_descuento_monto,
-            16.0,
-            y_actual + 1.0,
-            70.0,
-            1,
-            false,
+        flujo.fila(
+            Celda::light(&descuento_monto.2, 16.0),
+            Celda::bold(&precio_descuento_monto, 16.0),
         );
     }
-    pdf.set_paragraph(
-        &String::from("Total"),
-        16.0,
-        y_actual + 1.0,
-        70.0,
-        -1,
-        true,
-    );
-    let precio_total = format_clp(total);
-    y_actual = pdf.set_paragraph(
-        &precio_total,
-        16.0,
-        y_actual + 1.0,
-        70.0,
-        1,
-        false,
+    let precio_total = format_monto(total, moneda);
+    flujo.fila(
+        Celda::light("Total", 16.0),
+        Celda::bold(&precio_total, 16.0),
     );
+    // impuestos
+    flujo.bloque(|pdf, y| {
+        set_seccion_impuestos(pdf, orden, total, &opciones.impuestos, y)
+    });
     // disclaimer
-    let disclaimer =
-        String::from("* Total no incluye propina ni cuota de servicio.");
-    y_actual = pdf.set_paragraph(
-        &disclaimer,
+    flujo.parrafo(
+        "* Total no incluye propina ni cuota de servicio.",
         9.0,
-        y_actual + 1.0,
-        70.0,
-        -1,
+        Caja::izquierda(5.0, 70.0),
         true,
     );
+    // medios de pago
+    let en_moneda =
+        |monto| format_monto(a_unidad_minima(monto, moneda), moneda);
+    flujo.espacio(2.0);
+    for medio in &orden.pago.medios_pago {
+        let nombre_medio = medio.nombre.as_deref().unwrap_or("Otro");
+        flujo.fila(
+            Celda::bold(nombre_medio, 16.0),
+            Celda::bold(&en_moneda(medio.monto), 16.0),
+        );
+    }
+    if let (Some(paga_con), Some(vuelto)) =
+        (orden.pago.paga_con, orden.pago.vuelto(moneda))
+    {
+        flujo.fila(
+            Celda::light("Paga con", 14.0),
+            Celda::light(&en_moneda(paga_con), 14.0),
+        );
+        match vuelto {
+            Vuelto::Entregar(monto) => {
+                flujo.fila(
+                    Celda::bold("Vuelto", 16.0),
+                    Celda::bold(&format_monto(monto, moneda), 16.0),
+                );
+            }
+            // no se inventa un vuelto: se avisa para confirmar con el cliente
+            Vuelto::Falta(monto) => {
+                flujo.espacio(1.0).parrafo_estilo(
+                    &(String::from("PAGA CON MENOS: FALTAN ")
+                        + &format_monto(monto, moneda)),
+                    14.0,
+                    Caja::centrada(66.0),
+                    false,
+                    EstiloTexto::Invertido,
+                );
+            }
+        }
+    }
+    flujo.separacion(0.0);
+    // en delivery el repartidor debe cobrar el efectivo
+    let total_efectivo =
+        a_unidad_minima(orden.pago.total_efectivo(), moneda);
+    if orden.tipo_entrega == TipoEntrega::Delivery && total_efectivo > 0 {
+        let inicio_rect = flujo.y() + 6.0;
+        // monto a cobrar en caja de borde grueso
+        flujo
+            .espacio(7.0)
+            .parrafo("COBRAR EN EFECTIVO", 16.0, Caja::centrada(70.0), false)
+            .espacio(3.0)
+            .parrafo(
+                &format_monto(total_efectivo, moneda),
+                24.0,
+                Caja::centrada(70.0),
+                false,
+            )
+            .espacio(2.0)
+            .caja(
+                inicio_rect,
+                EstiloCaja {
+                    borde: Some(EstiloLinea {
+                        grosor: 0.7,
+                        ..EstiloLinea::default()
+                    }),
+                    radio: 2.0,
+                    ..EstiloCaja::default()
+                },
+            );
+    }
 
-    // medio de pago
-    let medio_pago = orden.pago.medio_pago.nombre.as_ref().unwrap();
-    y_actual = pdf.set_paragraph(
-        &medio_pago,
-        16.0,
-        y_actual + 5.0,
-        70.0,
-        1,
-        false,
-    );
-
-    let power_agil = String::from("powered by Agil");
-    pdf.set_paragraph(
-        &power_agil,
+    flujo.espacio(2.0).parrafo(
+        "powered by Agil",
         12.0,
-        y_actual + 2.0,
-        80.0,
-        0,
+        Caja::centrada(80.0),
         true,
     );
+    pdf
+}
+
+/// Largo del ticket de la orden y de cada sección, sin generar el PDF.
+pub fn medir_ticket(
+    orden: &IOrder,
+    opciones: &OpcionesTicket,
+) -> MedidaTicket {
+    layout_ticket(orden, opciones).medir()
+}
 
+fn pdf(orden: &IOrder, opciones: &OpcionesTicket) {
+    let mut pdf = layout_ticket(orden, opciones);
     pdf.init_draw();
     pdf.drow_all_obj();
-    pdf.save_pdf();
+    pdf.save_pdf("test_working.pdf");
 }
 
-fn main() {
-    let orden_ejemplo = IOrder {
-        comentario: Some("Orden de ejemplo".to_string()),
+/// TED de prueba (firmas de ejemplo, no válidas ante el SII).
+const TED_EJEMPLO: &str = "<TED version=\"1.0\"><DD><RE>76123456-7</RE>\
+<TD>39</TD><F>1234</F><FE>2024-12-25</FE><RR>66666666-6</RR>\
+<RSR>CLIENTE BOLETA</RSR><MNT>18500</MNT><IT1>Pizza Napolitana</IT1>\
+<CAF version=\"1.0\"><DA><RE>76123456-7</RE><RS>LA PIZZERIA SPA</RS>\
+<TD>39</TD><RNG><D>1</D><H>5000</H></RNG><FA>2024-12-01</FA><RSAPK>\
+<M>0a4O6Kbx8Qj3K4iWSP4w7KneZYeJ+g/prihYtIEolKt3cykSxl1zO8vSXu397QhTmsX7SB\
+EudTUx++2zDXBhZw==</M><E>Aw==</E></RSAPK><IDK>100</IDK></DA>\
+<FRMA algoritmo=\"SHA1withRSA\">g1AQX0sy8NJugX52k2hTJEZAE9Cuul6pqYBdFxj1N\
+17umW7zG/hAavCALKByHzdYAfZ3LhGTXCai5zNxOo4lDQ==</FRMA></CAF>\
+<TSTED>2024-12-25T14:05:00</TSTED></DD><FRMT algoritmo=\"SHA1withRSA\">\
+GbmDcS9e/jVC2LsLIe1iRV12Bf6lxsILtbQiCkh6mbjckFCJ7fj/kakFTS06Jo8iS4HXvJj3oY\
+Zuref7f9Wcfg==</FRMT></TED>";
+
+/// Orden de ejemplo usada por la CLI.
+fn crear_orden_ejemplo() -> IOrder {
+    IOrder {
+        comentario: Some(
+            "Orden de ejemplo\nAvisar a pedidos.lapizzeria.ejemplo@gmail.com"
+                .to_string(),
+        ),
         items: vec![
             Item {
                 cantidad: 2.0,
                 nombre: "Pizza Napolitana".to_string(),
                 precio: 2500.0,
+                precio_lista: None,
                 opciones: Some(vec![
                     IOpciones {
                         modificador: "Extra 1".to_string(),
@@ -609,33 +1085,101 @@ fn main() {
                     },
                 ]),
                 comentario: Some("Sin aceitunas, por favor por favor por favor por favor por favor por favor!!!".to_string()),
+                impuesto: CategoriaImpuesto::Afecto,
+                alergenos: None,
+                sub_items: None,
             },
             Item {
                 cantidad: 5.0,
                 nombre: "πz²a".to_string(),
                 precio: 2500.0,
+                precio_lista: None,
+                opciones: Some(vec![]),
+                comentario: Some("".to_string()),
+                impuesto: CategoriaImpuesto::Afecto,
+                alergenos: Some(vec![Alergeno::Gluten]),
+                sub_items: None,
+            },
+            Item {
+                cantidad: 1.0,
+                nombre: "Promo Familiar".to_string(),
+                precio: 12990.0,
+                precio_lista: None,
+                opciones: Some(vec![]),
+                comentario: Some("".to_string()),
+                impuesto: CategoriaImpuesto::Afecto,
+                alergenos: None,
+                sub_items: Some(vec![
+                    Item {
+                        cantidad: 2.0,
+                        nombre: "Pizza Familiar Pepperoni".to_string(),
+                        precio: 0.0,
+                        precio_lista: None,
+                        opciones: Some(vec![IOpciones {
+                            modificador: "Masa".to_string(),
+                            cantidad: 1,
+                            opcion: "Delgada".to_string(),
+                        }]),
+                        comentario: Some("".to_string()),
+                        impuesto: CategoriaImpuesto::Afecto,
+                        alergenos: None,
+                        sub_items: None,
+                    },
+                    Item {
+                        cantidad: 1.0,
+                        nombre: "Bebida 1.5L".to_string(),
+                        precio: 0.0,
+                        precio_lista: None,
+                        opciones: Some(vec![]),
+                        comentario: Some("".to_string()),
+                        impuesto: CategoriaImpuesto::Afecto,
+                        alergenos: None,
+                        sub_items: None,
+                    },
+                ]),
+            },
+            Item {
+                cantidad: 1.0,
+                nombre: "Agua Mineral (promo)".to_string(),
+                precio: 1000.0,
+                precio_lista: Some(1500.0),
                 opciones: Some(vec![]),
                 comentario: Some("".to_string()),
+                impuesto: CategoriaImpuesto::Exento,
+                alergenos: None,
+                sub_items: None,
             },
         ],
-        sub_total: 5000,
-        gastos_envio: 1000,
+        sub_total: 5000.0,
+        gastos_envio: 1000.0,
+        moneda: None,
+        entrega_programada: None,
+        alergenos: None,
         dscto_cupon_gasto_envio: 0.0,
         dscto_cupon_subtotal: 0.0,
         dscto_puntos: 0.0,
         cupones: None,
         fechas: Fechas {
-            fecha_salida_cocina_estimada: "2024-12-25T14:30:00Z".to_string(),
-            fecha_entrega_min: "2024-12-25T14:31:00Z".to_string(),
+            fecha_salida_cocina_estimada: "2024-12-26T12:40:00Z".to_string(),
+            fecha_entrega_min: "2024-12-26T13:00:00Z".to_string(),
+            fecha_entrega_max: Some("2024-12-26T13:30:00Z".to_string()),
             fecha_pago: "2024-12-25T14:05:00Z".to_string(),
             tz: "America/Santiago".to_string(),
         },
-        tipo_entrega: TipoEntrega { id: 1 }, // 1 = Delivery, 2 = Retiro, etc.
+        tipo_entrega: TipoEntrega::Delivery,
+        mesa: None,
         drop_off: Some(DropOff {
             tipo_entrega: Some("tipo entrega viene como string".to_string()),
             direccion: Some("Av Siemrpe Viva 420, titirilquen".to_string()),
         }),
-        courier: Courier { id_courier: -2 }, // -2 = Reparto Propio, ejemplo
+        courier: Courier {
+            id_courier: -2, // -2 = Reparto Propio, ejemplo
+            repartidor: Some(Repartidor {
+                nombre: Some("Tomás Soto".to_string()),
+                telefono: Some("+56 9 8765 4321".to_string()),
+            }),
+            eta_retiro: Some("2024-12-26T12:45:00Z".to_string()),
+        },
         cliente: Cliente {
             telefono: Some("+56 9 1234 5678".to_string()),
             nombre: Some("Pablo Diego José Francisco de Paula Juan Nepomuceno María de los Remedios Cipriano de la Santísima Trinidad Ruiz y Picasso".to_string()),
@@ -647,20 +1191,214 @@ fn main() {
         plataforma: Plataforma {
             codigo: Some("AGIL".to_string()),
             nombre: Some("Agil".to_string()),
+            id_orden: None,
         },
         correlativo: 9,
         codigo: "P42069".to_string(),
         comercio: Comercio {
             nombre: Some("La Pizzería".to_string()),
+            moneda: Some("CLP".to_string()),
         },
         pago: Pago {
-            medio_pago: MedioPago {
-                nombre: Some("Tarjeta".to_string()),
-            },
+            medios_pago: vec![
+                MedioPago {
+                    nombre: Some("Tarjeta".to_string()),
+                    monto: 3000.0,
+                    efectivo: false,
+                },
+                MedioPago {
+                    nombre: Some("Efectivo".to_string()),
+                    monto: 3000.0,
+                    efectivo: true,
+                },
+            ],
+            paga_con: Some(5000.0),
         },
-    };
+    }
+}
 
-    pdf(&orden_ejemplo);
+fn main() {
+    let orden_ejemplo = crear_orden_ejemplo();
+
+    // `cargo run -- boleta [ted.xml]` genera la boleta electrónica,
+    // `modificacion` y `anulacion` los tickets de cambio para cocina y
+    // `cocina` el ticket con los combos separados en sus componentes y
+    // `local` el ticket de una orden para consumo en local, `rappi` el de
+    // una orden de agregador y `cierre` el cierre de caja del turno (PDF y
+    // CSV). `medir` muestra el largo del ticket por sección sin generarlo y
+    // `json` su lista de dibujo. Con `depurar` antes de la variante (p.ej.
+    // `cargo run -- depurar boleta`) el PDF sale con las guías del layout
+    let mut args: Vec<String> = std::env::args().collect();
+    let depurar = args.get(1).is_some_and(|arg| arg == "depurar");
+    if depurar {
+        args.remove(1);
+    }
+    let opciones = || OpcionesTicket {
+        depurar,
+        ..OpcionesTicket::default()
+    };
+    match args.get(1).map(String::as_str) {
+        Some("boleta") => {
+            let ted_xml = match args.get(2) {
+                Some(ruta) => leer_ted_archivo(ruta),
+                None => TED_EJEMPLO.to_string(),
+            };
+            let datos_boleta = DatosBoleta {
+                ted_xml,
+                razon_social: Some("La Pizzería SpA".to_string()),
+                giro: Some("Restaurantes".to_string()),
+                direccion: Some("Av Siempre Viva 742, Santiago".to_string()),
+                unidad_sii: Some("S.I.I. - SANTIAGO CENTRO".to_string()),
+                resolucion_numero: 80,
+                resolucion_anio: 2014,
+            };
+            let boleta =
+                pdf_boleta(&orden_ejemplo, &datos_boleta, &opciones());
+            if let Err(err) = boleta {
+                eprintln!("No se pudo generar la boleta: {}", err);
+                std::process::exit(1);
+            }
+        }
+        Some("modificacion") => {
+            // la orden actual cambia cantidades, quita y agrega ítems
+            let mut orden_actual = crear_orden_ejemplo();
+            orden_actual.items[0].cantidad = 3.0;
+            orden_actual.items.remove(1);
+            orden_actual.items.push(Item {
+                cantidad: 1.0,
+                nombre: "Bebida 1.5L".to_string(),
+                precio: 2000.0,
+                precio_lista: None,
+                opciones: Some(vec![]),
+                comentario: Some("".to_string()),
+                impuesto: CategoriaImpuesto::Afecto,
+                alergenos: None,
+                sub_items: None,
+            });
+            pdf_modificacion(
+                &orden_ejemplo,
+                &orden_actual,
+                TipoModificacion::Modificado,
+                &opciones(),
+            );
+        }
+        Some("cocina") => {
+            let opciones = OpcionesTicket {
+                explotar_combos: true,
+                ..opciones()
+            };
+            pdf(&orden_ejemplo, &opciones);
+        }
+        Some("local") => {
+            // la misma orden servida en la mesa
+            let orden_local = IOrder {
+                tipo_entrega: TipoEntrega::ConsumoEnLocal,
+                drop_off: None,
+                mesa: Some(Mesa {
+                    numero: "12".to_string(),
+                    mesero: Some("Juanita".to_string()),
+                    cubiertos: Some(4),
+                }),
+                ..crear_orden_ejemplo()
+            };
+            pdf(&orden_local, &opciones());
+        }
+        Some("cierre") => {
+            // el turno: la orden de ejemplo, la misma en mesa y un retiro
+            let ordenes = vec![
+                crear_orden_ejemplo(),
+                IOrder {
+                    tipo_entrega: TipoEntrega::ConsumoEnLocal,
+                    drop_off: None,
+                    gastos_envio: 0.0,
+                    codigo: "P42070".to_string(),
+                    correlativo: 10,
+                    ..crear_orden_ejemplo()
+                },
+                IOrder {
+                    tipo_entrega: TipoEntrega::Retiro,
+                    drop_off: None,
+                    gastos_envio: 0.0,
+                    dscto_puntos: 500.0,
+                    codigo: "P42071".to_string(),
+                    correlativo: 11,
+                    fechas: Fechas {
+                        fecha_salida_cocina_estimada: "2024-12-25T21:10:00Z"
+                            .to_string(),
+                        fecha_entrega_min: "2024-12-25T21:20:00Z".to_string(),
+                        fecha_entrega_max: None,
+                        fecha_pago: "2024-12-25T20:55:00Z".to_string(),
+                        tz: "America/Santiago".to_string(),
+                    },
+                    ..crear_orden_ejemplo()
+                },
+            ];
+            let resumen = match ResumenCierre::desde_ordenes(&ordenes) {
+                Ok(resumen) => resumen,
+                Err(err) => {
+                    eprintln!("No se pudo generar el cierre: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            std::fs::write("cierre.csv", resumen.csv())
+                .expect("No se pudo escribir cierre.csv");
+            pdf_cierre(&resumen, &opciones());
+        }
+        Some("rappi") => {
+            // orden de un agregador, con su marca e id en el encabezado
+            let orden_rappi = IOrder {
+                plataforma: Plataforma {
+                    codigo: Some("RAPPI".to_string()),
+                    nombre: Some("Rappi".to_string()),
+                    id_orden: Some("218-554-9031".to_string()),
+                },
+                courier: Courier {
+                    id_courier: 1,
+                    repartidor: Some(Repartidor {
+                        nombre: Some("Carla Díaz".to_string()),
+                        telefono: None,
+                    }),
+                    eta_retiro: Some("2024-12-26T12:50:00Z".to_string()),
+                },
+                ..crear_orden_ejemplo()
+            };
+            pdf(&orden_rappi, &opciones());
+        }
+        Some("medir") => {
+            let medida =
+                medir_ticket(&orden_ejemplo, &OpcionesTicket::default());
+            for seccion in &medida.secciones {
+                println!("{}\t{:.1}", seccion.nombre, seccion.alto());
+            }
+            println!("total\t{:.1}", medida.alto_mm);
+        }
+        Some("json") => {
+            let ticket =
+                layout_ticket(&orden_ejemplo, &OpcionesTicket::default());
+            print!("{}", ticket.lista_dibujo().json());
+        }
+        Some("anulacion") => pdf_modificacion(
+            &orden_ejemplo,
+            &orden_ejemplo,
+            TipoModificacion::Anulado,
+            &opciones(),
+        ),
+        _ => {
+            // el historial define si es la primera impresión o una reimpresión
+            let historial = HistorialImpresiones::new(RUTA_HISTORIAL);
+            let ahora = chrono::Local::now().to_rfc3339();
+            let registro = historial
+                .registrar(&orden_ejemplo.codigo, &ahora)
+                .expect("No se pudo escribir el historial de impresiones");
+            let opciones = OpcionesTicket {
+                intento_impresion: registro.intento,
+                fecha_impresion_original: registro.fecha_original,
+                fecha_impresion: ahora,
+                ..opciones()
+            };
+            pdf(&orden_ejemplo, &opciones);
+        }
+    }
     // println!("espacio creado")
 }
 
@@ -699,3 +1437,149 @@ fn main() {
 // - envio (cupon)
 // - cupon
 // - puntos
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pago_efectivo(monto: f32, paga_con: f32) -> Pago {
+        Pago {
+            medios_pago: vec![MedioPago {
+                nombre: Some("Efectivo".to_string()),
+                monto,
+                efectivo: true,
+            }],
+            paga_con: Some(paga_con),
+        }
+    }
+
+    #[test]
+    fn vuelto_y_faltante() {
+        let pago = pago_efectivo(3000.0, 5000.0);
+        assert_eq!(pago.vuelto(Moneda::Clp), Some(Vuelto::Entregar(2000)));
+        let justo = pago_efectivo(12.5, 12.5);
+        assert_eq!(justo.vuelto(Moneda::Pen), Some(Vuelto::Entregar(0)));
+        let corto = pago_efectivo(12.5, 10.0);
+        assert_eq!(corto.vuelto(Moneda::Pen), Some(Vuelto::Falta(250)));
+        let sin_monto = Pago {
+            paga_con: None,
+            ..pago_efectivo(3000.0, 0.0)
+        };
+        assert_eq!(sin_monto.vuelto(Moneda::Clp), None);
+    }
+
+    #[test]
+    fn tipo_entrega_desde_id() {
+        assert_eq!(TipoEntrega::try_from(1), Ok(TipoEntrega::Delivery));
+        assert_eq!(TipoEntrega::try_from(2), Ok(TipoEntrega::Retiro));
+        assert_eq!(TipoEntrega::try_from(3), Ok(TipoEntrega::ConsumoEnLocal));
+        assert_eq!(TipoEntrega::try_from(4), Ok(TipoEntrega::DriveThru));
+        assert_eq!(TipoEntrega::try_from(9), Err(9));
+        assert_eq!(
+            TipoEntrega::try_from(0).unwrap_or_default(),
+            TipoEntrega::Delivery
+        );
+    }
+
+    fn item(cantidad: f32, nombre: &str, sub_items: Option<Vec<Item>>) -> Item {
+        Item {
+            cantidad,
+            nombre: nombre.to_string(),
+            precio: 1000.0,
+            precio_lista: None,
+            opciones: Some(vec![]),
+            comentario: Some("".to_string()),
+            impuesto: CategoriaImpuesto::Afecto,
+            alergenos: None,
+            sub_items,
+        }
+    }
+
+    fn opcion(modificador: &str, valor: &str) -> IOpciones {
+        IOpciones {
+            modificador: modificador.to_string(),
+            cantidad: 1,
+            opcion: valor.to_string(),
+        }
+    }
+
+    #[test]
+    fn explotar_combos_multiplica_cantidades() {
+        let latas = Some(vec![item(3.0, "Lata", None)]);
+        let bebidas = item(2.0, "Pack bebidas", latas);
+        let combo = item(
+            2.0,
+            "Combo",
+            Some(vec![item(2.0, "Pizza", None), bebidas]),
+        );
+        let explotados = explotar_combos(&[combo, item(1.0, "Agua", None)]);
+        let resumen: Vec<(&str, f32, f32)> = explotados
+            .iter()
+            .map(|item| (item.nombre.as_str(), item.cantidad, item.precio))
+            .collect();
+        assert_eq!(
+            resumen,
+            vec![
+                ("Pizza", 4.0, 0.0),
+                ("Lata", 12.0, 0.0),
+                ("Agua", 1.0, 1000.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn explotar_combos_traspasa_opciones_y_alergenos() {
+        let mut pizza = item(1.0, "Pizza", None);
+        pizza.opciones = Some(vec![opcion("Masa", "Delgada")]);
+        pizza.comentario = Some("Bien cocida".to_string());
+        let mut combo = item(
+            1.0,
+            "Combo",
+            Some(vec![pizza, item(1.0, "Bebida", None)]),
+        );
+        combo.opciones = Some(vec![opcion("Salsa", "Picante")]);
+        combo.comentario = Some("Sin cebolla".to_string());
+        combo.alergenos = Some(vec![Alergeno::Mani]);
+
+        let explotados = explotar_combos(&[combo]);
+        let opciones: Vec<&str> = explotados[0]
+            .opciones
+            .iter()
+            .flatten()
+            .map(|opcion| opcion.opcion.as_str())
+            .collect();
+        assert_eq!(opciones, vec!["Delgada", "Picante"]);
+        assert_eq!(
+            explotados[0].comentario.as_deref(),
+            Some("Bien cocida. Sin cebolla")
+        );
+        assert!(explotados[1].opciones.iter().flatten().next().is_none());
+        for componente in &explotados {
+            assert_eq!(componente.alergenos, Some(vec![Alergeno::Mani]));
+        }
+    }
+
+    #[test]
+    fn medir_ticket_por_seccion() {
+        let orden = crear_orden_ejemplo();
+        let medida = medir_ticket(&orden, &OpcionesTicket::default());
+        let nombres: Vec<&str> =
+            medida.secciones.iter().map(|s| s.nombre.as_str()).collect();
+        assert_eq!(
+            nombres,
+            vec!["encabezado", "cliente", "entrega", "pedidos", "pagos"]
+        );
+        let suma: f32 = medida.secciones.iter().map(|s| s.alto()).sum();
+        assert!((suma - medida.alto_mm).abs() < 0.01);
+        assert!(medida.secciones.iter().all(|s| s.alto() > 0.0));
+
+        // el de cocina no lleva la sección de pagos
+        let cocina = OpcionesTicket {
+            explotar_combos: true,
+            ..OpcionesTicket::default()
+        };
+        let medida_cocina = medir_ticket(&orden, &cocina);
+        assert!(medida_cocina.secciones.iter().all(|s| s.nombre != "pagos"));
+        assert!(medida_cocina.alto_mm < medida.alto_mm);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::moneda::moneda_orden;
+use crate::pdf_resources::{
+    Caja, Celda, EstiloTexto, OpcionesFila, PdfResources,
+};
+use crate::{set_item, IOrder, Item, OpcionesTicket};
+
+/// Tipo de ticket de cambio que se envía a cocina.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TipoModificacion {
+    Modificado,
+    Anulado,
+}
+
+/// Diferencia de un ítem entre la versión original y la actual de la orden.
+pub enum CambioItem<'a> {
+    Agregado(&'a Item),
+    Eliminado(&'a Item),
+    Modificado { antes: &'a Item, despues: &'a Item },
+}
+
+/// Compara cantidad, modificadores, comentario y componentes de dos
+/// versiones de un ítem.
+fn item_igual(antes: &Item, despues: &Item) -> bool {
+    let opciones = |item: &Item| -> Vec<(String, i32, String)> {
+        item.opciones
+            .iter()
+            .flatten()
+            .map(|o| (o.modificador.clone(), o.cantidad, o.opcion.clone()))
+            .collect()
+    };
+    let sub_antes = antes.sub_items.as_deref().unwrap_or_default();
+    let sub_despues = despues.sub_items.as_deref().unwrap_or_default();
+    antes.cantidad == despues.cantidad
+        && antes.comentario == despues.comentario
+        && opciones(antes) == opciones(despues)
+        && sub_antes.len() == sub_despues.len()
+        && sub_antes
+            .iter()
+            .zip(sub_despues)
+            .all(|(a, d)| a.nombre == d.nombre && item_igual(a, d))
+}
+
+/// Calcula los ítems agregados, eliminados y modificados entre dos versiones
+/// de la orden. Los ítems se emparejan por nombre, en orden de aparición.
+pub fn diff_items<'a>(
+    original: &'a IOrder,
+    actual: &'a IOrder,
+) -> Vec<CambioItem<'a>> {
+    let mut cambios = Vec::new();
+    let mut usados = vec![false; actual.items.len()];
+
+    for antes in &original.items {
+        let pareja = actual
+            .items
+            .iter()
+            .enumerate()
+            .position(|(i, item)| !usados[i] && item.nombre == antes.nombre);
+        match pareja {
+            Some(i) => {
+                usados[i] = true;
+                let despues = &actual.items[i];
+                if !item_igual(antes, despues) {
+                    cambios.push(CambioItem::Modificado { antes, despues });
+                }
+            }
+            None => cambios.push(CambioItem::Eliminado(antes)),
+        }
+    }
+    for (i, item) in actual.items.iter().enumerate() {
+        if !usados[i] {
+            cambios.push(CambioItem::Agregado(item));
+        }
+    }
+    cambios
+}
+
+/// Genera el ticket de cocina con los cambios de una orden ya impresa.
+///
+/// En una anulación se listan tachados todos los ítems de la orden original.
+pub fn pdf_modificacion(
+    original: &IOrder,
+    actual: &IOrder,
+    tipo: TipoModificacion,
+    opciones: &OpcionesTicket,
+) {
+    let mut pdf = PdfResources::new();
+    pdf.depurar = opciones.depurar;
+    let moneda = moneda_orden(actual);
+    let mut y_actual = 0.;
+
+    // CUERPO 0: banner
+    y_actual = match tipo {
+        TipoModificacion::Modificado => pdf.set_paragraph(
+            &String::from("MODIFICADO"),
+            28.0,
+            y_actual + 14.0,
+            Caja::centrada(70.0),
+            false,
+        ),
+        TipoModificacion::Anulado => pdf.set_paragraph_estilo(
+            &String::from("ANULADO"),
+            28.0,
+            y_actual + 14.0,
+            Caja::centrada(70.0),
+            false,
+            EstiloTexto::Invertido,
+        ),
+    };
+
+    // datos para identificar la orden en cocina
+    let comercio_nombre = actual.comercio.nombre.as_ref().unwrap();
+    y_actual = pdf.set_paragraph(
+        comercio_nombre,
+        16.0,
+        y_actual + 5.0,
+        Caja::centrada(70.0),
+        false,
+    );
+    let codigo_pedido = String::from("#") + &actual.codigo;
+    y_actual = pdf.set_row(
+        &Celda::light(&codigo_pedido, 16.0),
+        &Celda::bold(&actual.correlativo.to_string(), 32.0),
+        y_actual + 2.0,
+        &OpcionesFila::default(),
+    );
+    let cliente_nombre = actual.cliente.nombre.as_ref().unwrap();
+    y_actual = pdf.set_paragraph(
+        cliente_nombre,
+        14.0,
+        y_actual + 1.0,
+        Caja::centrada(70.0),
+        true,
+    );
+    pdf.set_separacion(y_actual + 2.0, "cubiertos");
+    y_actual += 7.0;
+
+    // CUERPO 1: cambios
+    let cambios: Vec<CambioItem> = match tipo {
+        TipoModificacion::Modificado => diff_items(original, actual),
+        TipoModificacion::Anulado => {
+            original.items.iter().map(CambioItem::Eliminado).collect()
+        }
+    };
+    for cambio in &cambios {
+        let (titulo, items) = match cambio {
+            CambioItem::Agregado(item) => {
+                ("AGREGADO", vec![(*item, EstiloTexto::Normal)])
+            }
+            CambioItem::Eliminado(item) => {
+                ("ELIMINADO", vec![(*item, EstiloTexto::Tachado)])
+            }
+            CambioItem::Modificado { antes, despues } => (
+                "CAMBIO",
+                vec![
+                    (*antes, EstiloTexto::Tachado),
+                    (*despues, EstiloTexto::Normal),
+                ],
+            ),
+        };
+        y_actual = pdf.set_paragraph_estilo(
+            &String::from(titulo),
+            12.0,
+            y_actual + 6.0,
+            Caja::izquierda(5.0, 70.0),
+            false,
+            EstiloTexto::Subrayado,
+        );
+        for (item, estilo) in items {
+            y_actual = set_item(&mut pdf, item, moneda, y_actual, estilo);
+        }
+        pdf.set_linea(y_actual + 1.0);
+    }
+
+    pdf.set_paragraph(
+        &String::from("powered by Agil"),
+        12.0,
+        y_actual + 6.0,
+        Caja::centrada(80.0),
+        true,
+    );
+
+    pdf.init_draw();
+    pdf.drow_all_obj();
+    pdf.save_pdf("modificacion.pdf");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crear_orden_ejemplo;
+
+    /// Cada cambio como su tipo y el nombre del ítem.
+    fn resumen(cambios: &[CambioItem]) -> Vec<(&'static str, String)> {
+        cambios
+            .iter()
+            .map(|cambio| match cambio {
+                CambioItem::Agregado(item) => ("agregado", item.nombre.clone()),
+                CambioItem::Eliminado(item) => {
+                    ("eliminado", item.nombre.clone())
+                }
+                CambioItem::Modificado { despues, .. } => {
+                    ("modificado", despues.nombre.clone())
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sin_cambios() {
+        let orden = crear_orden_ejemplo();
+        assert!(diff_items(&orden, &crear_orden_ejemplo()).is_empty());
+    }
+
+    #[test]
+    fn agregados_eliminados_y_modificados() {
+        let original = crear_orden_ejemplo();
+        let mut actual = crear_orden_ejemplo();
+        actual.items[0].cantidad = 3.0;
+        actual.items.remove(1);
+        let mut bebida = actual.items[2].clone();
+        bebida.nombre = "Bebida 1.5L".to_string();
+        actual.items.push(bebida);
+        assert_eq!(
+            resumen(&diff_items(&original, &actual)),
+            vec![
+                ("modificado", "Pizza Napolitana".to_string()),
+                ("eliminado", "πz²a".to_string()),
+                ("agregado", "Bebida 1.5L".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn cambios_en_comentario_y_componentes() {
+        let original = crear_orden_ejemplo();
+        let mut actual = crear_orden_ejemplo();
+        actual.items[1].comentario = Some("Bien cocida".to_string());
+        actual.items[2].sub_items.as_mut().unwrap()[0].opciones = None;
+        assert_eq!(
+            resumen(&diff_items(&original, &actual)),
+            vec![
+                ("modificado", "πz²a".to_string()),
+                ("modificado", "Promo Familiar".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nombres_repetidos_se_emparejan_en_orden() {
+        let mut original = crear_orden_ejemplo();
+        let repetido = original.items[0].clone();
+        original.items.push(repetido);
+        let mut actual = crear_orden_ejemplo();
+        actual.items[0].cantidad = 1.0;
+        assert_eq!(
+            resumen(&diff_items(&original, &actual)),
+            vec![
+                ("modificado", "Pizza Napolitana".to_string()),
+                ("eliminado", "Pizza Napolitana".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::IOrder;
+
+/// Monedas en las que operan los comercios (código ISO 4217).
+///
+/// Las órdenes traen los montos en unidades de la moneda (12.50 soles); para
+/// sumarlos e imprimirlos se pasan una sola vez a la unidad mínima (centavos
+/// para PEN, MXN y USD; pesos para CLP y COP) con `a_unidad_minima`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Moneda {
+    #[default]
+    Clp,
+    Pen,
+    Cop,
+    Mxn,
+    Usd,
+}
+
+/// Reglas de impresión de una moneda.
+struct FormatoMoneda {
+    simbolo: &'static str,
+    /// Espacio entre el símbolo y el número ("S/ 10.00").
+    espacio: bool,
+    decimales: u32,
+    sep_miles: char,
+    sep_decimal: char,
+}
+
+impl Moneda {
+    /// Busca la moneda por su código ISO ("CLP", "pen", ...).
+    pub fn desde_codigo(codigo: &str) -> Option<Moneda> {
+        match codigo.trim().to_uppercase().as_str() {
+            "CLP" => Some(Moneda::Clp),
+            "PEN" => Some(Moneda::Pen),
+            "COP" => Some(Moneda::Cop),
+            "MXN" => Some(Moneda::Mxn),
+            "USD" => Some(Moneda::Usd),
+            _ => None,
+        }
+    }
+
+    /// Código ISO de la moneda ("CLP").
+    pub fn codigo(&self) -> &'static str {
+        match self {
+            Moneda::Clp => "CLP",
+            Moneda::Pen => "PEN",
+            Moneda::Cop => "COP",
+            Moneda::Mxn => "MXN",
+            Moneda::Usd => "USD",
+        }
+    }
+
+    fn formato(&self) -> FormatoMoneda {
+        match self {
+            Moneda::Clp => FormatoMoneda {
+                simbolo: "$",
+                espacio: false,
+                decimales: 0,
+                sep_miles: '.',
+                sep_decimal: ',',
+            },
+            Moneda::Pen => FormatoMoneda {
+                simbolo: "S/",
+                espacio: true,
+                decimales: 2,
+                sep_miles: ',',
+                sep_decimal: '.',
+            },
+            // ISO 4217 define 2 decimales, pero los centavos no circulan y
+            // los precios se publican siempre en pesos enteros.
+            Moneda::Cop => FormatoMoneda {
+                simbolo: "$",
+                espacio: false,
+                decimales: 0,
+                sep_miles: '.',
+                sep_decimal: ',',
+            },
+            Moneda::Mxn => FormatoMoneda {
+                simbolo: "$",
+                espacio: false,
+                decimales: 2,
+                sep_miles: ',',
+                sep_decimal: '.',
+            },
+            Moneda::Usd => FormatoMoneda {
+                simbolo: "US$",
+                espacio: false,
+                decimales: 2,
+                sep_miles: ',',
+                sep_decimal: '.',
+            },
+        }
+    }
+}
+
+/// Moneda con la que se imprime la orden: la de la orden si viene, si no
+/// la del comercio, y CLP por defecto.
+pub fn moneda_orden(orden: &IOrder) -> Moneda {
+    // un código desconocido en la orden no tapa el del comercio
+    orden
+        .moneda
+        .as_deref()
+        .and_then(Moneda::desde_codigo)
+        .or_else(|| {
+            orden.comercio.moneda.as_deref().and_then(Moneda::desde_codigo)
+        })
+        .unwrap_or_default()
+}
+
+/// Pasa un monto en unidades de la moneda a su unidad mínima, redondeando.
+///
+/// Ej: `a_unidad_minima(12.5, Moneda::Pen)` => 1250,
+/// `a_unidad_minima(990.6, Moneda::Clp)` => 991.
+pub fn a_unidad_minima(monto: f32, moneda: Moneda) -> i64 {
+    let factor = 10f64.powi(moneda.formato().decimales as i32);
+    (monto as f64 * factor).round() as i64
+}
+
+/// Formatea un monto expresado en la unidad mínima de la moneda.
+///
+/// Ej: `format_monto(123450, Moneda::Pen)` => "S/ 1,234.50",
+/// `format_monto(-5000, Moneda::Clp)` => "-$5.000".
+pub fn format_monto(monto: i64, moneda: Moneda) -> String {
+    let formato = moneda.formato();
+    let divisor = 10i64.pow(formato.decimales);
+    let absoluto = monto.unsigned_abs();
+    let enteros = (absoluto / divisor as u64).to_string();
+    let fraccion = absoluto % divisor as u64;
+
+    let mut formatted = String::new();
+    for (count, ch) in enteros.chars().rev().enumerate() {
+        if count > 0 && count % 3 == 0 {
+            formatted.push(formato.sep_miles);
+        }
+        formatted.push(ch);
+    }
+    formatted = formatted.chars().rev().collect();
+    if formato.decimales > 0 {
+        formatted.push(formato.sep_decimal);
+        formatted += &format!(
+            "{:0ancho$}",
+            fraccion,
+            ancho = formato.decimales as usize
+        );
+    }
+
+    let signo = if monto < 0 {
+        "-"
+    } else {
+        ""
+    };
+    let espacio = if formato.espacio {
+        " "
+    } else {
+        ""
+    };
+    format!(
+        "{}{}{}{}",
+        signo, formato.simbolo, espacio, formatted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_monto_por_moneda() {
+        assert_eq!(format_monto(1234567, Moneda::Clp), "$1.234.567");
+        assert_eq!(format_monto(123450, Moneda::Pen), "S/ 1,234.50");
+        assert_eq!(format_monto(1500000, Moneda::Cop), "$1.500.000");
+        assert_eq!(format_monto(9905, Moneda::Mxn), "$99.05");
+        assert_eq!(format_monto(7, Moneda::Usd), "US$0.07");
+        assert_eq!(format_monto(0, Moneda::Clp), "$0");
+    }
+
+    #[test]
+    fn format_monto_negativos() {
+        assert_eq!(format_monto(-5000, Moneda::Clp), "-$5.000");
+        assert_eq!(format_monto(-1250, Moneda::Pen), "-S/ 12.50");
+        assert_eq!(format_monto(-99, Moneda::Usd), "-US$0.99");
+    }
+
+    #[test]
+    fn a_unidad_minima_redondea() {
+        assert_eq!(a_unidad_minima(12.5, Moneda::Pen), 1250);
+        assert_eq!(a_unidad_minima(0.29, Moneda::Usd), 29);
+        assert_eq!(a_unidad_minima(990.6, Moneda::Clp), 991);
+        assert_eq!(a_unidad_minima(-3.5, Moneda::Mxn), -350);
+        assert_eq!(
+            format_monto(a_unidad_minima(12.5, Moneda::Pen), Moneda::Pen),
+            "S/ 12.50"
+        );
+    }
+}
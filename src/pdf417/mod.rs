@@ -0,0 +1,242 @@
+//! Codificador PDF417 (ISO/IEC 15438) en Rust puro.
+//!
+//! Solo implementa lo que necesita el timbre electrónico del SII:
+//! compactación binaria (byte), corrección de errores Reed-Solomon sobre
+//! GF(929) y el armado de la matriz de módulos fila por fila.
+
+mod tablas;
+use tablas::PATRONES;
+
+const PATRON_INICIO: u32 = 0b11111111010101000;
+const PATRON_FIN: u32 = 0b111111101000101001;
+const LARGO_INICIO: u32 = 17;
+const LARGO_FIN: u32 = 18;
+
+const LATCH_BYTE: u16 = 901;
+const LATCH_BYTE_MULTIPLO_6: u16 = 924;
+const RELLENO: u16 = 900;
+
+pub const MAX_FILAS: usize = 90;
+pub const MAX_COLUMNAS: usize = 30;
+const MAX_CODEWORDS: usize = 928;
+
+/// Símbolo PDF417 ya codificado, listo para dibujar.
+pub struct Pdf417 {
+    /// Módulos por fila (`true` = barra negra), incluyendo inicio y fin.
+    pub modulos: Vec<Vec<bool>>,
+    pub filas: usize,
+}
+
+impl Pdf417 {
+    /// Ancho del símbolo en módulos.
+    pub fn ancho_modulos(&self) -> usize {
+        self.modulos.first().map_or(0, |fila| fila.len())
+    }
+}
+
+/// Ancho en módulos de un símbolo con `columnas` columnas de datos.
+pub fn ancho_modulos(columnas: usize) -> usize {
+    // inicio + indicador izq + datos + indicador der + fin
+    17 * (columnas + 3) + LARGO_FIN as usize
+}
+
+/// Cantidad de codewords de corrección para un nivel (0..=8).
+pub fn codewords_ecc(nivel: u8) -> usize {
+    2usize << nivel
+}
+
+/// Codifica `datos` con compactación binaria.
+///
+/// Devuelve los codewords de datos sin el descriptor de largo, empezando por
+/// el latch a modo byte.
+pub fn compactar_bytes(datos: &[u8]) -> Vec<u16> {
+    let mut codewords = Vec::new();
+    if datos.len().is_multiple_of(6) {
+        codewords.push(LATCH_BYTE_MULTIPLO_6);
+    } else {
+        codewords.push(LATCH_BYTE);
+    }
+
+    let mut grupos = datos.chunks_exact(6);
+    for grupo in &mut grupos {
+        // 6 bytes en base 256 son 5 codewords en base 900
+        let mut valor: u64 =
+            grupo.iter().fold(0, |acc, &b| (acc << 8) | b as u64);
+        let mut cinco = [0u16; 5];
+        for cw in cinco.iter_mut().rev() {
+            *cw = (valor % 900) as u16;
+            valor /= 900;
+        }
+        codewords.extend_from_slice(&cinco);
+    }
+    codewords.extend(grupos.remainder().iter().map(|&b| b as u16));
+    codewords
+}
+
+/// Coeficientes del polinomio generador `(x - 3)(x - 3²)...(x - 3^k)`,
+/// del término independiente al de grado `k - 1` (el de grado `k` es 1).
+fn polinomio_generador(k: usize) -> Vec<u32> {
+    let mut coef: Vec<u32> = vec![1];
+    let mut raiz = 1u32;
+    for _ in 0..k {
+        raiz = raiz * 3 % 929;
+        let neg = 929 - raiz;
+        let mut nuevo = vec![0u32; coef.len() + 1];
+        for (i, c) in coef.iter().enumerate() {
+            nuevo[i + 1] = (nuevo[i + 1] + c) % 929;
+            nuevo[i] = (nuevo[i] + c * neg) % 929;
+        }
+        coef = nuevo;
+    }
+    coef.pop();
+    coef
+}
+
+/// Calcula los codewords Reed-Solomon de `datos` para el nivel dado.
+pub fn calcular_ecc(datos: &[u16], nivel: u8) -> Vec<u16> {
+    let k = codewords_ecc(nivel);
+    let a = polinomio_generador(k);
+    let mut e = vec![0u32; k];
+    for &d in datos {
+        let t1 = (d as u32 + e[k - 1]) % 929;
+        for j in (1..k).rev() {
+            let t2 = t1 * a[j] % 929;
+            e[j] = (e[j - 1] + 929 - t2) % 929;
+        }
+        e[0] = (929 - t1 * a[0] % 929) % 929;
+    }
+    e.iter().rev().map(|&v| ((929 - v) % 929) as u16).collect()
+}
+
+/// Codifica `datos` en un PDF417 de `columnas` columnas con el nivel de
+/// corrección `nivel`.
+///
+/// Devuelve `None` si los datos no caben en el símbolo.
+pub fn codificar(datos: &[u8], columnas: usize, nivel: u8) -> Option<Pdf417> {
+    if columnas == 0 || columnas > MAX_COLUMNAS || nivel > 8 {
+        return None;
+    }
+    let compactados = compactar_bytes(datos);
+    let ecc = codewords_ecc(nivel);
+
+    // descriptor de largo + datos
+    let minimo = 1 + compactados.len() + ecc;
+    let filas = minimo.div_ceil(columnas).max(3);
+    if filas > MAX_FILAS || filas * columnas > MAX_CODEWORDS {
+        return None;
+    }
+    let largo_datos = filas * columnas - ecc;
+
+    let mut codewords: Vec<u16> = Vec::with_capacity(filas * columnas);
+    codewords.push(largo_datos as u16);
+    codewords.extend_from_slice(&compactados);
+    codewords.resize(largo_datos, RELLENO);
+    let correccion = calcular_ecc(&codewords, nivel);
+    codewords.extend_from_slice(&correccion);
+
+    let modulos = codewords
+        .chunks(columnas)
+        .enumerate()
+        .map(|(fila, datos_fila)| {
+            armar_fila(fila, datos_fila, filas, columnas, nivel)
+        })
+        .collect();
+
+    Some(Pdf417 { modulos, filas })
+}
+
+/// Arma los módulos de una fila: inicio, indicador izquierdo, datos,
+/// indicador derecho y fin.
+fn armar_fila(
+    fila: usize,
+    datos: &[u16],
+    filas: usize,
+    columnas: usize,
+    nivel: u8,
+) -> Vec<bool> {
+    let cluster = fila % 3;
+    let base = 30 * (fila / 3);
+    let valor_filas = (filas - 1) / 3;
+    let valor_nivel = nivel as usize * 3 + (filas - 1) % 3;
+    let valor_columnas = columnas - 1;
+    let (izquierdo, derecho) = match cluster {
+        0 => (valor_filas, valor_columnas),
+        1 => (valor_nivel, valor_filas),
+        _ => (valor_columnas, valor_nivel),
+    };
+
+    let mut modulos = Vec::with_capacity(ancho_modulos(columnas));
+    let mut agregar = |patron: u32, largo: u32| {
+        for bit in (0..largo).rev() {
+            modulos.push(patron >> bit & 1 == 1);
+        }
+    };
+    agregar(PATRON_INICIO, LARGO_INICIO);
+    agregar(PATRONES[cluster][base + izquierdo], 17);
+    for &cw in datos {
+        agregar(PATRONES[cluster][cw as usize], 17);
+    }
+    agregar(PATRONES[cluster][base + derecho], 17);
+    agregar(PATRON_FIN, LARGO_FIN);
+    modulos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coeficientes_generador() {
+        // tabla de coeficientes de la norma para los niveles 0 y 1
+        assert_eq!(polinomio_generador(codewords_ecc(0)), vec![27, 917]);
+        assert_eq!(
+            polinomio_generador(codewords_ecc(1)),
+            vec![522, 568, 723, 809]
+        );
+    }
+
+    #[test]
+    fn compactar_grupo_de_6_bytes() {
+        assert_eq!(
+            compactar_bytes(&[1, 2, 3, 4, 5, 6]),
+            vec![LATCH_BYTE_MULTIPLO_6, 1, 620, 89, 74, 846]
+        );
+    }
+
+    #[test]
+    fn compactar_bytes_sobrantes() {
+        // el byte que no completa un grupo de 6 va tal cual
+        assert_eq!(
+            compactar_bytes(&[1, 2, 3, 4, 5, 6, 200]),
+            vec![LATCH_BYTE, 1, 620, 89, 74, 846, 200]
+        );
+        assert_eq!(compactar_bytes(b"ab"), vec![LATCH_BYTE, 97, 98]);
+    }
+
+    #[test]
+    fn ecc_del_ejemplo_de_la_norma() {
+        // "PDF417" en modo texto con descriptor de largo, nivel 1
+        assert_eq!(
+            calcular_ecc(&[5, 453, 178, 121, 239], 1),
+            vec![452, 327, 657, 619]
+        );
+    }
+
+    #[test]
+    fn codificar_respeta_limites() {
+        let simbolo = codificar(b"TED", 4, 2).unwrap();
+        assert_eq!(simbolo.ancho_modulos(), ancho_modulos(4));
+        assert!(simbolo.filas >= 3);
+        assert!(codificar(b"TED", 0, 2).is_none());
+        assert!(codificar(b"TED", 4, 9).is_none());
+    }
+
+    #[test]
+    fn codificar_rechaza_datos_que_no_caben() {
+        // 851 codewords de datos + 64 de corrección piden 31 filas de 30
+        // columnas: 930 codewords, dos más de los que admite el símbolo
+        assert!(codificar(&[0u8; 1020], 30, 5).is_none());
+        assert!(codificar(&[0u8; 5000], 30, 5).is_none());
+        assert!(codificar(&[0u8; 960], 30, 5).is_some());
+    }
+}
@@ -0,0 +1,161 @@
+use super::{
+    alinear_lineas, medir_texto_mm, partir_lineas, Caja, Celda, EstiloTexto,
+    FontData, ParrafoLine, PdfResources,
+};
+
+/// Paso con el que se prueba cada tamaño de letra, en puntos.
+const PASO_FONT: f32 = 0.5;
+const ELIPSIS: &str = "…";
+
+/// Tamaño de letra automático para textos de largo variable, como el
+/// nombre del cliente: el más grande entre `font_min` y `font_max` con el
+/// que el texto entra en `max_lineas`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AjusteTexto {
+    pub max_lineas: usize,
+    pub font_min: f32,
+    pub font_max: f32,
+}
+
+impl PdfResources<'_> {
+    /// Tamaño de letra más grande de `ajuste` con el que `texto` entra en
+    /// sus líneas de `ancho_mm`, o `font_min` si no entra con ninguno.
+    pub fn tamano_ajustado(
+        &self,
+        texto: &str,
+        ajuste: &AjusteTexto,
+        ancho_mm: f32,
+        light: bool,
+    ) -> f32 {
+        let font_data = self.fuente(light);
+        let mut font_size = ajuste.font_max;
+        while font_size - PASO_FONT >= ajuste.font_min
+            && partir_lineas(texto, font_size, ancho_mm, font_data).len()
+                > ajuste.max_lineas
+        {
+            font_size -= PASO_FONT;
+        }
+        font_size.max(ajuste.font_min)
+    }
+
+    /// Igual que `set_paragraph`, pero con el tamaño de letra que elige
+    /// `ajuste`. Si al mínimo el texto sigue sin entrar, se corta en
+    /// `max_lineas` y la última termina en "…".
+    pub fn set_paragraph_ajustado(
+        &mut self,
+        text: &str,
+        ajuste: &AjusteTexto,
+        y_inicial: f32,
+        caja: Caja,
+        light: bool,
+    ) -> f32 {
+        let ancho = caja.ancho_texto();
+        let font_size = self.tamano_ajustado(text, ajuste, ancho, light);
+        let font_data = self.fuente(light);
+        let mut lines = partir_lineas(text, font_size, ancho, font_data);
+        if lines.len() > ajuste.max_lineas {
+            cortar_con_elipsis(
+                &mut lines,
+                ajuste.max_lineas,
+                ancho,
+                font_size,
+                font_data,
+            );
+        }
+        alinear_lineas(&mut lines, &caja, font_size, font_data);
+
+        let celda = Celda {
+            texto: text,
+            font_size,
+            light,
+            estilo: EstiloTexto::Normal,
+        };
+        self.agregar_lineas(lines, &celda, Some(caja), y_inicial)
+    }
+}
+
+/// Deja `max_lineas` líneas y termina la última en "…", quitándole letras
+/// hasta que entre en `ancho_mm`.
+pub(super) fn cortar_con_elipsis(
+    lines: &mut Vec<ParrafoLine>,
+    max_lineas: usize,
+    ancho_mm: f32,
+    font_size: f32,
+    font_data: &FontData,
+) {
+    lines.truncate(max_lineas);
+    let Some(ultima) = lines.last_mut() else {
+        return;
+    };
+    // el guion de una palabra cortada ya no corresponde
+    let mut texto = ultima.text.trim_end_matches('-').to_string();
+    let mut cortada = texto.trim_end().to_string() + ELIPSIS;
+    while medir_texto_mm(&cortada, font_size, font_data) > ancho_mm
+        && texto.pop().is_some()
+    {
+        cortada = texto.trim_end().to_string() + ELIPSIS;
+    }
+    ultima.width_mm = medir_texto_mm(&cortada, font_size, font_data);
+    ultima.text = cortada;
+    ultima.fin_renglon = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AJUSTE: AjusteTexto = AjusteTexto {
+        max_lineas: 2,
+        font_min: 16.0,
+        font_max: 24.0,
+    };
+
+    #[test]
+    fn tamano_ajustado_entre_minimo_y_maximo() {
+        let pdf = PdfResources::new();
+        let tamano = |texto| pdf.tamano_ajustado(texto, &AJUSTE, 70.0, false);
+        assert_eq!(tamano("Ana"), 24.0);
+        let medio = tamano("Restaurante La Pizzería de Don Pepe");
+        assert!(medio < 24.0 && medio > 16.0, "{}", medio);
+        let lineas = partir_lineas(
+            "Restaurante La Pizzería de Don Pepe",
+            medio,
+            70.0,
+            &pdf.bold,
+        );
+        assert!(lineas.len() <= AJUSTE.max_lineas);
+        assert_eq!(tamano(&"muy largo ".repeat(20)), 16.0);
+    }
+
+    #[test]
+    fn set_paragraph_ajustado_corta_con_elipsis() {
+        let mut pdf = PdfResources::new();
+        let caja = Caja::centrada(70.0);
+        let texto = "Restaurante ".repeat(20);
+        pdf.set_paragraph_ajustado(&texto, &AJUSTE, 0.0, caja, false);
+        let parrafo = &pdf.paragraphs[0];
+        assert_eq!(parrafo.font_size, AJUSTE.font_min);
+        assert_eq!(parrafo.lines.len(), AJUSTE.max_lineas);
+        let ultima = parrafo.lines.last().unwrap();
+        assert!(ultima.text.ends_with(ELIPSIS), "{}", ultima.text);
+        assert!(ultima.width_mm <= caja.ancho_texto());
+    }
+
+    #[test]
+    fn elipsis_no_deja_el_guion_de_la_palabra_cortada() {
+        let pdf = PdfResources::new();
+        let mut lineas = vec![
+            ParrafoLine {
+                text: "Electro-".to_string(),
+                ..ParrafoLine::default()
+            },
+            ParrafoLine {
+                text: "domésticos".to_string(),
+                ..ParrafoLine::default()
+            },
+        ];
+        cortar_con_elipsis(&mut lineas, 1, 70.0, 16.0, &pdf.bold);
+        assert_eq!(lineas.len(), 1);
+        assert_eq!(lineas[0].text, "Electro…");
+    }
+}
@@ -0,0 +1,205 @@
+use super::{medir_texto_mm, FontData, ParrafoLine, ANCHO_PAPEL};
+
+/// Máximo que se estira el espacio entre palabras al justificar, en veces
+/// el espacio normal; una línea que quedaría más suelta va a la izquierda.
+const ESTIRAMIENTO_MAXIMO: f32 = 4.0;
+
+/// Alineación horizontal del texto dentro de su caja.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Alineacion {
+    #[default]
+    Izquierda,
+    Centro,
+    Derecha,
+    /// Reparte el espacio sobrante entre las palabras para llenar el ancho.
+    /// La última línea de cada párrafo queda a la izquierda.
+    Justificado,
+}
+
+/// Caja donde se acomoda un texto: borde izquierdo y ancho en mm, relleno
+/// a cada lado y alineación de las líneas.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Caja {
+    pub x: f32,
+    pub ancho: f32,
+    pub relleno: f32,
+    pub alineacion: Alineacion,
+}
+
+impl Caja {
+    pub fn izquierda(x: f32, ancho: f32) -> Self {
+        Caja {
+            x,
+            ancho,
+            relleno: 0.0,
+            alineacion: Alineacion::Izquierda,
+        }
+    }
+
+    pub fn derecha(x: f32, ancho: f32) -> Self {
+        Caja {
+            alineacion: Alineacion::Derecha,
+            ..Caja::izquierda(x, ancho)
+        }
+    }
+
+    pub fn justificada(x: f32, ancho: f32) -> Self {
+        Caja {
+            alineacion: Alineacion::Justificado,
+            ..Caja::izquierda(x, ancho)
+        }
+    }
+
+    /// Caja de `ancho` centrada en el papel, con el texto centrado.
+    pub fn centrada(ancho: f32) -> Self {
+        Caja {
+            alineacion: Alineacion::Centro,
+            ..Caja::izquierda((ANCHO_PAPEL - ancho) / 2.0, ancho)
+        }
+    }
+
+    /// La misma caja con `mm` de relleno a cada lado.
+    pub fn con_relleno(self, mm: f32) -> Self {
+        Caja {
+            relleno: mm,
+            ..self
+        }
+    }
+
+    /// Ancho disponible para el texto, descontado el relleno.
+    pub fn ancho_texto(&self) -> f32 {
+        (self.ancho - 2.0 * self.relleno).max(0.0)
+    }
+}
+
+/// Ubica en la caja las líneas ya partidas a su `ancho_texto`. En las
+/// justificadas se calcula además la X de cada palabra.
+pub(super) fn alinear_lineas(
+    lineas: &mut [ParrafoLine],
+    caja: &Caja,
+    font_size: f32,
+    font_data: &FontData,
+) {
+    let inicio = caja.x + caja.relleno;
+    let ancho = caja.ancho_texto();
+    for linea in lineas {
+        linea.x_position = match caja.alineacion {
+            Alineacion::Izquierda | Alineacion::Justificado => inicio,
+            Alineacion::Centro => inicio + (ancho - linea.width_mm) / 2.0,
+            Alineacion::Derecha => inicio + ancho - linea.width_mm,
+        };
+        if caja.alineacion == Alineacion::Justificado && !linea.fin_renglon {
+            justificar(linea, ancho, font_size, font_data);
+        }
+    }
+}
+
+/// Reparte el espacio que le sobra a la línea entre sus palabras, salvo que
+/// quede demasiado suelta.
+fn justificar(
+    linea: &mut ParrafoLine,
+    ancho: f32,
+    font_size: f32,
+    font_data: &FontData,
+) {
+    let anchos: Vec<f32> = linea
+        .text
+        .split(' ')
+        .map(|palabra| medir_texto_mm(palabra, font_size, font_data))
+        .collect();
+    if anchos.len() < 2 {
+        return;
+    }
+    let espacio =
+        (ancho - anchos.iter().sum::<f32>()) / (anchos.len() - 1) as f32;
+    if espacio > ESTIRAMIENTO_MAXIMO * medir_texto_mm(" ", font_size, font_data)
+    {
+        return;
+    }
+    let mut x = linea.x_position;
+    linea.x_palabras = anchos
+        .iter()
+        .map(|ancho_palabra| {
+            let x_palabra = x;
+            x += ancho_palabra + espacio;
+            x_palabra
+        })
+        .collect();
+    linea.width_mm = ancho;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PdfResources;
+    use super::*;
+
+    fn linea(texto: &str, font: &FontData, fin_renglon: bool) -> ParrafoLine {
+        ParrafoLine {
+            text: texto.to_string(),
+            width_mm: medir_texto_mm(texto, 12.0, font),
+            fin_renglon,
+            ..ParrafoLine::default()
+        }
+    }
+
+    fn cerca(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn alinea_dentro_del_relleno() {
+        let pdf = PdfResources::new();
+        let ancho = linea("Total", &pdf.light, true).width_mm;
+        let posicion = |alineacion| {
+            let caja = Caja {
+                alineacion,
+                ..Caja::izquierda(5.0, 70.0).con_relleno(2.0)
+            };
+            let mut lineas = [linea("Total", &pdf.light, true)];
+            alinear_lineas(&mut lineas, &caja, 12.0, &pdf.light);
+            lineas[0].x_position
+        };
+        let mut lineas = [linea("Total", &pdf.light, true)];
+        assert!(cerca(posicion(Alineacion::Izquierda), 7.0));
+        assert!(cerca(posicion(Alineacion::Derecha), 73.0 - ancho));
+        assert!(cerca(posicion(Alineacion::Centro), 40.0 - ancho / 2.0));
+        // la centrada queda al medio del papel
+        alinear_lineas(&mut lineas, &Caja::centrada(70.0), 12.0, &pdf.light);
+        assert!(cerca(lineas[0].x_position, (ANCHO_PAPEL - ancho) / 2.0));
+    }
+
+    #[test]
+    fn justificado_llena_el_ancho_salvo_la_ultima_linea() {
+        let pdf = PdfResources::new();
+        let caja = Caja::justificada(5.0, 40.0);
+        let mut lineas = vec![
+            linea("Dejar en conserjería del", &pdf.light, false),
+            linea("edificio", &pdf.light, true),
+        ];
+        alinear_lineas(&mut lineas, &caja, 12.0, &pdf.light);
+
+        let primera = &lineas[0];
+        assert!(cerca(primera.width_mm, 40.0));
+        assert_eq!(primera.x_palabras.len(), 4);
+        assert!(cerca(primera.x_palabras[0], 5.0));
+        let ultima_palabra = medir_texto_mm("del", 12.0, &pdf.light);
+        assert!(cerca(primera.x_palabras[3] + ultima_palabra, 45.0));
+        assert!(lineas[1].x_palabras.is_empty());
+        assert!(cerca(lineas[1].x_position, 5.0));
+    }
+
+    #[test]
+    fn justificado_no_estira_lineas_muy_sueltas() {
+        let pdf = PdfResources::new();
+        let mut lineas = vec![linea("Sin sal", &pdf.light, false)];
+        let ancho = lineas[0].width_mm;
+        alinear_lineas(
+            &mut lineas,
+            &Caja::justificada(5.0, 70.0),
+            12.0,
+            &pdf.light,
+        );
+        assert!(lineas[0].x_palabras.is_empty());
+        assert!(cerca(lineas[0].width_mm, ancho));
+    }
+}
@@ -0,0 +1,188 @@
+//! Guías para ajustar el layout a la vista: se dibujan encima del ticket con
+//! líneas finas de color.
+
+use super::figuras::{EstiloCaja, EstiloLinea, Figura, Forma, Tono, Trazo};
+use super::lista::Elemento;
+use super::{ParagraphData, PdfResources};
+
+/// Grosor de las guías, en mm.
+const GROSOR_GUIA: f32 = 0.1;
+/// Borde de las líneas de cada párrafo.
+const TONO_PARRAFO: Tono = Tono {
+    rojo: 0.0,
+    verde: 0.4,
+    azul: 1.0,
+};
+/// Líneas base.
+const TONO_BASE: Tono = Tono {
+    rojo: 1.0,
+    verde: 0.2,
+    azul: 0.2,
+};
+/// Ancho disponible para el texto en la caja del párrafo.
+const TONO_CAJA: Tono = Tono {
+    rojo: 0.0,
+    verde: 0.7,
+    azul: 0.3,
+};
+const TONO_IMAGEN: Tono = Tono {
+    rojo: 0.8,
+    verde: 0.0,
+    azul: 0.8,
+};
+const TONO_REGLA: Tono = Tono {
+    rojo: 0.0,
+    verde: 0.6,
+    azul: 0.8,
+};
+/// Tamaño de los números de la regla, en puntos.
+const FONT_REGLA: f32 = 4.0;
+const GRIS_REGLA: f32 = 0.5;
+
+impl PdfResources<'_> {
+    /// Guías del layout: el contorno de las líneas de cada párrafo, sus
+    /// líneas base, el ancho de su caja, el marco de cada ícono y una regla
+    /// en mm en el borde izquierdo.
+    pub(super) fn guias_depuracion(&self) -> Vec<Elemento> {
+        let mut guias = Vec::new();
+        for parrafo in &self.paragraphs {
+            guias_parrafo(&mut guias, parrafo);
+        }
+        for imagen in &self.imgs {
+            guias.push(marco(
+                imagen.x,
+                imagen.y - imagen.alto,
+                imagen.ancho,
+                imagen.y,
+                TONO_IMAGEN,
+                Trazo::Continuo,
+            ));
+        }
+        regla(&mut guias, self.page_height);
+        guias
+    }
+}
+
+fn guias_parrafo(guias: &mut Vec<Elemento>, parrafo: &ParagraphData) {
+    let lineas = parrafo.lines.iter().filter(|linea| !linea.text.is_empty());
+    let inicio = lineas
+        .clone()
+        .map(|linea| linea.x_position)
+        .fold(f32::INFINITY, f32::min);
+    let fin = lineas
+        .map(|linea| linea.x_position + linea.width_mm)
+        .fold(f32::NEG_INFINITY, f32::max);
+    if inicio > fin {
+        return;
+    }
+    let y_fin =
+        parrafo.y_inicio + parrafo.lines.len() as f32 * parrafo.interlinea;
+
+    if let Some(caja) = parrafo.caja {
+        guias.push(marco(
+            caja.x + caja.relleno,
+            parrafo.y_inicio,
+            caja.ancho_texto(),
+            y_fin,
+            TONO_CAJA,
+            Trazo::Segmentado {
+                largo: 1.0,
+                espacio: 1.0,
+            },
+        ));
+    }
+    guias.push(marco(
+        inicio,
+        parrafo.y_inicio,
+        fin - inicio,
+        y_fin,
+        TONO_PARRAFO,
+        Trazo::Continuo,
+    ));
+    for (i, linea) in parrafo.lines.iter().enumerate() {
+        if linea.text.is_empty() {
+            continue;
+        }
+        let y = parrafo.y_base + i as f32 * parrafo.interlinea;
+        guias.push(guia(
+            Forma::Linea {
+                x1: linea.x_position,
+                y1: y,
+                x2: linea.x_position + linea.width_mm,
+                y2: y,
+            },
+            TONO_BASE,
+            Trazo::Continuo,
+        ));
+    }
+}
+
+/// Marca cada mm en el borde izquierdo, más larga cada 5 y con el número
+/// cada 10.
+fn regla(guias: &mut Vec<Elemento>, alto_mm: f32) {
+    for mm in 0..=alto_mm as u32 {
+        let largo = if mm % 10 == 0 {
+            3.0
+        } else if mm % 5 == 0 {
+            2.0
+        } else {
+            1.0
+        };
+        let y = mm as f32;
+        guias.push(guia(
+            Forma::Linea {
+                x1: 0.0,
+                y1: y,
+                x2: largo,
+                y2: y,
+            },
+            TONO_REGLA,
+            Trazo::Continuo,
+        ));
+        if mm % 10 == 0 && mm > 0 {
+            guias.push(Elemento::Texto {
+                texto: mm.to_string(),
+                light: true,
+                font_size: FONT_REGLA,
+                x: 3.3,
+                y: y + 0.5,
+                gris: GRIS_REGLA,
+            });
+        }
+    }
+}
+
+fn marco(
+    x: f32,
+    y_inicio: f32,
+    ancho: f32,
+    y_fin: f32,
+    tono: Tono,
+    trazo: Trazo,
+) -> Elemento {
+    guia(
+        Forma::Caja {
+            x,
+            y_inicio,
+            ancho,
+            y_fin,
+        },
+        tono,
+        trazo,
+    )
+}
+
+fn guia(forma: Forma, tono: Tono, trazo: Trazo) -> Elemento {
+    Elemento::Figura(Figura {
+        forma,
+        estilo: EstiloCaja {
+            borde: Some(EstiloLinea {
+                grosor: GROSOR_GUIA,
+                trazo,
+                tono,
+            }),
+            relleno: None,
+            radio: 0.0,
+        },
+    })
+}
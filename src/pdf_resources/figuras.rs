@@ -0,0 +1,344 @@
+use printpdf::path::{PaintMode, WindingOrder};
+use printpdf::*;
+
+use super::{
+    negro, tinta, PdfResources, MARGEN_DERECHO, MARGEN_IZQUIERDO, PT_A_MM,
+};
+
+/// Grosor de línea por defecto, en mm (el punto que usa printpdf).
+const GROSOR_NORMAL: f32 = 0.35;
+/// Distancia de los puntos de control de Bézier para aproximar un cuarto de
+/// círculo, en veces el radio.
+const KAPPA: f32 = 0.5523;
+/// Gris del fondo de los comentarios (0 = negro, 1 = blanco).
+const GRIS_COMENTARIO: f32 = 0.85;
+
+/// Patrón con que se traza una línea.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Trazo {
+    #[default]
+    Continuo,
+    /// Guiones de `largo` separados por `espacio`, en mm.
+    Segmentado { largo: f32, espacio: f32 },
+    /// Puntos redondos del grosor de la línea.
+    Punteado,
+}
+
+/// Color de un trazo, de 0 a 1 por canal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tono {
+    pub rojo: f32,
+    pub verde: f32,
+    pub azul: f32,
+}
+
+impl Tono {
+    pub const NEGRO: Tono = Tono {
+        rojo: 0.0,
+        verde: 0.0,
+        azul: 0.0,
+    };
+}
+
+/// Grosor (en mm), patrón y color de una línea o del borde de una caja.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EstiloLinea {
+    pub grosor: f32,
+    pub trazo: Trazo,
+    pub tono: Tono,
+}
+
+impl Default for EstiloLinea {
+    fn default() -> Self {
+        EstiloLinea {
+            grosor: GROSOR_NORMAL,
+            trazo: Trazo::Continuo,
+            tono: Tono::NEGRO,
+        }
+    }
+}
+
+impl EstiloLinea {
+    pub fn segmentada(grosor: f32) -> Self {
+        EstiloLinea {
+            grosor,
+            trazo: Trazo::Segmentado {
+                largo: 1.5,
+                espacio: 1.0,
+            },
+            ..EstiloLinea::default()
+        }
+    }
+
+    pub fn punteada(grosor: f32) -> Self {
+        EstiloLinea {
+            grosor,
+            trazo: Trazo::Punteado,
+            ..EstiloLinea::default()
+        }
+    }
+}
+
+/// Cómo se dibuja una caja: borde, relleno gris y radio de las esquinas.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EstiloCaja {
+    pub borde: Option<EstiloLinea>,
+    /// Nivel de gris del relleno (0 = negro, 1 = blanco); `None` sin relleno.
+    pub relleno: Option<f32>,
+    /// Radio de las esquinas, en mm (0 = esquinas rectas).
+    pub radio: f32,
+}
+
+impl Default for EstiloCaja {
+    fn default() -> Self {
+        EstiloCaja {
+            borde: Some(EstiloLinea::default()),
+            relleno: None,
+            radio: 0.0,
+        }
+    }
+}
+
+impl EstiloCaja {
+    /// Caja con fondo gris claro y esquinas redondeadas, para comentarios.
+    pub fn sombreada() -> Self {
+        EstiloCaja {
+            relleno: Some(GRIS_COMENTARIO),
+            radio: 2.0,
+            ..EstiloCaja::default()
+        }
+    }
+}
+
+/// Forma en coordenadas del ticket (mm, y crece hacia abajo desde arriba).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Forma {
+    Linea {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+    Caja {
+        x: f32,
+        y_inicio: f32,
+        ancho: f32,
+        y_fin: f32,
+    },
+}
+
+/// Línea o caja con su estilo, que se dibuja como un trazado del PDF.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Figura {
+    pub forma: Forma,
+    pub estilo: EstiloCaja,
+}
+
+impl Figura {
+    /// Rectángulo relleno de `gris`, sin borde.
+    pub fn relleno(
+        x: f32,
+        y_inicio: f32,
+        ancho: f32,
+        y_fin: f32,
+        gris: f32,
+    ) -> Self {
+        Figura {
+            forma: Forma::Caja {
+                x,
+                y_inicio,
+                ancho,
+                y_fin,
+            },
+            estilo: EstiloCaja {
+                borde: None,
+                relleno: Some(gris),
+                radio: 0.0,
+            },
+        }
+    }
+}
+
+impl PdfResources<'_> {
+    /// Línea horizontal de margen a margen.
+    pub fn set_linea_estilo(&mut self, y: f32, estilo: EstiloLinea) {
+        self.figuras.push(Figura {
+            forma: Forma::Linea {
+                x1: MARGEN_IZQUIERDO,
+                y1: y,
+                x2: MARGEN_DERECHO,
+                y2: y,
+            },
+            estilo: EstiloCaja {
+                borde: Some(estilo),
+                ..EstiloCaja::default()
+            },
+        });
+    }
+
+    /// Caja de margen a margen entre `y_inicio` y `y_fin`. Su relleno queda
+    /// detrás del texto.
+    pub fn set_caja(&mut self, y_inicio: f32, y_fin: f32, estilo: EstiloCaja) {
+        self.figuras.push(Figura {
+            forma: Forma::Caja {
+                x: MARGEN_IZQUIERDO,
+                y_inicio,
+                ancho: MARGEN_DERECHO - MARGEN_IZQUIERDO,
+                y_fin,
+            },
+            estilo,
+        });
+    }
+
+    /// Dibuja una figura y deja el estado gráfico como estaba.
+    pub fn draw_figura(
+        current_layer: &PdfLayerReference,
+        figura: &Figura,
+        page_height: f32,
+    ) {
+        let estilo = figura.estilo;
+        if let Some(gris) = estilo.relleno {
+            current_layer.set_fill_color(tinta(gris));
+        }
+        if let Some(borde) = estilo.borde {
+            if borde.tono != Tono::NEGRO {
+                current_layer.set_outline_color(Color::Rgb(Rgb::new(
+                    borde.tono.rojo,
+                    borde.tono.verde,
+                    borde.tono.azul,
+                    None,
+                )));
+            }
+            current_layer.set_outline_thickness(borde.grosor / PT_A_MM);
+            aplicar_trazo(current_layer, borde);
+        }
+
+        match figura.forma {
+            Forma::Linea { x1, y1, x2, y2 } => {
+                if estilo.borde.is_some() {
+                    current_layer.add_line(Line {
+                        points: vec![
+                            (
+                                Point::new(Mm(x1), Mm(page_height - y1)),
+                                false,
+                            ),
+                            (
+                                Point::new(Mm(x2), Mm(page_height - y2)),
+                                false,
+                            ),
+                        ],
+                        is_closed: false,
+                    });
+                }
+            }
+            Forma::Caja {
+                x,
+                y_inicio,
+                ancho,
+                y_fin,
+            } => {
+                let mode = match (estilo.borde, estilo.relleno) {
+                    (Some(_), Some(_)) => Some(PaintMode::FillStroke),
+                    (Some(_), None) => Some(PaintMode::Stroke),
+                    (None, Some(_)) => Some(PaintMode::Fill),
+                    (None, None) => None,
+                };
+                if let Some(mode) = mode {
+                    current_layer.add_polygon(Polygon {
+                        rings: vec![contorno_redondeado(
+                            x,
+                            page_height - y_inicio,
+                            x + ancho,
+                            page_height - y_fin,
+                            estilo.radio,
+                        )],
+                        mode,
+                        winding_order: WindingOrder::NonZero,
+                    });
+                }
+            }
+        }
+
+        if estilo.relleno.is_some() {
+            current_layer.set_fill_color(negro());
+        }
+        if let Some(borde) = estilo.borde {
+            if borde.tono != Tono::NEGRO {
+                current_layer.set_outline_color(negro());
+            }
+            current_layer.set_outline_thickness(GROSOR_NORMAL / PT_A_MM);
+            current_layer.set_line_dash_pattern(LineDashPattern::default());
+            current_layer.set_line_cap_style(LineCapStyle::Butt);
+        }
+    }
+}
+
+/// Patrón de guiones del PDF, que va en puntos enteros.
+fn aplicar_trazo(current_layer: &PdfLayerReference, borde: EstiloLinea) {
+    let a_puntos = |mm: f32| ((mm / PT_A_MM).round() as i64).max(1);
+    match borde.trazo {
+        Trazo::Continuo => {}
+        Trazo::Segmentado { largo, espacio } => {
+            current_layer.set_line_dash_pattern(LineDashPattern {
+                dash_1: Some(a_puntos(largo)),
+                gap_1: Some(a_puntos(espacio)),
+                ..LineDashPattern::default()
+            });
+        }
+        Trazo::Punteado => {
+            // guiones de largo cero con punta redonda quedan como puntos
+            current_layer.set_line_cap_style(LineCapStyle::Round);
+            current_layer.set_line_dash_pattern(LineDashPattern {
+                dash_1: Some(0),
+                gap_1: Some(a_puntos(3.0 * borde.grosor)),
+                ..LineDashPattern::default()
+            });
+        }
+    }
+}
+
+/// Contorno de un rectángulo en coordenadas PDF (y crece hacia arriba), con
+/// las esquinas como cuartos de círculo de `radio`. Cada curva va marcada
+/// como printpdf espera: el punto de inicio y el primer control en `true`.
+fn contorno_redondeado(
+    l: f32,
+    arriba: f32,
+    r: f32,
+    abajo: f32,
+    radio: f32,
+) -> Vec<(Point, bool)> {
+    let radio = radio.min((r - l) / 2.0).min((arriba - abajo) / 2.0);
+    let punto = |x: f32, y: f32, curva: bool| (Point::new(Mm(x), Mm(y)), curva);
+    if radio <= 0.0 {
+        return vec![
+            punto(l, arriba, false),
+            punto(r, arriba, false),
+            punto(r, abajo, false),
+            punto(l, abajo, false),
+        ];
+    }
+    let k = KAPPA * radio;
+    vec![
+        punto(l + radio, arriba, false),
+        // esquina superior derecha
+        punto(r - radio, arriba, true),
+        punto(r - radio + k, arriba, true),
+        punto(r, arriba - radio + k, false),
+        punto(r, arriba - radio, false),
+        // esquina inferior derecha
+        punto(r, abajo + radio, true),
+        punto(r, abajo + radio - k, true),
+        punto(r - radio + k, abajo, false),
+        punto(r - radio, abajo, false),
+        // esquina inferior izquierda
+        punto(l + radio, abajo, true),
+        punto(l + radio - k, abajo, true),
+        punto(l, abajo + radio - k, false),
+        punto(l, abajo + radio, false),
+        // esquina superior izquierda
+        punto(l, arriba - radio, true),
+        punto(l, arriba - radio + k, true),
+        punto(l + radio - k, arriba, false),
+        punto(l + radio, arriba, false),
+    ]
+}
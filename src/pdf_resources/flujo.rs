@@ -0,0 +1,171 @@
+use super::{
+    AjusteTexto, Caja, Celda, EstiloCaja, EstiloLinea, EstiloTexto,
+    OpcionesFila, PdfResources,
+};
+
+/// Layout en flujo vertical: apila párrafos y filas uno bajo otro llevando
+/// la posición Y, para armar secciones sin coordenadas a mano.
+///
+/// Las líneas, cajas, íconos y marcas se ubican en la Y actual sin moverla;
+/// lo que avanza son los párrafos, las filas y los espacios.
+pub struct Flujo<'p, 'a> {
+    pdf: &'p mut PdfResources<'a>,
+    y: f32,
+    /// Espacio que se deja antes de cada párrafo o fila.
+    separacion: f32,
+}
+
+impl<'a> PdfResources<'a> {
+    /// Empieza un flujo vertical en `y`.
+    pub fn flujo(&mut self, y: f32) -> Flujo<'_, 'a> {
+        Flujo {
+            pdf: self,
+            y,
+            separacion: 0.0,
+        }
+    }
+}
+
+impl<'a> Flujo<'_, 'a> {
+    /// Posición Y donde termina lo apilado hasta ahora.
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    /// Fija el espacio que se deja antes de cada párrafo o fila.
+    pub fn separacion(&mut self, mm: f32) -> &mut Self {
+        self.separacion = mm;
+        self
+    }
+
+    /// Deja un espacio adicional de `mm`.
+    pub fn espacio(&mut self, mm: f32) -> &mut Self {
+        self.y += mm;
+        self
+    }
+
+    /// Apila un párrafo acomodado en `caja`.
+    pub fn parrafo(
+        &mut self,
+        texto: &str,
+        font_size: f32,
+        caja: Caja,
+        light: bool,
+    ) -> &mut Self {
+        self.y = self.pdf.set_paragraph(
+            texto,
+            font_size,
+            self.y + self.separacion,
+            caja,
+            light,
+        );
+        self
+    }
+
+    /// Apila un párrafo con estilo (invertido, subrayado o tachado).
+    pub fn parrafo_estilo(
+        &mut self,
+        texto: &str,
+        font_size: f32,
+        caja: Caja,
+        light: bool,
+        estilo: EstiloTexto,
+    ) -> &mut Self {
+        self.y = self.pdf.set_paragraph_estilo(
+            texto,
+            font_size,
+            self.y + self.separacion,
+            caja,
+            light,
+            estilo,
+        );
+        self
+    }
+
+    /// Apila un párrafo con el tamaño de letra ajustado a su largo.
+    pub fn parrafo_ajustado(
+        &mut self,
+        texto: &str,
+        ajuste: &AjusteTexto,
+        caja: Caja,
+        light: bool,
+    ) -> &mut Self {
+        self.y = self.pdf.set_paragraph_ajustado(
+            texto,
+            ajuste,
+            self.y + self.separacion,
+            caja,
+            light,
+        );
+        self
+    }
+
+    /// Apila una fila con una celda a la izquierda y otra a la derecha. El
+    /// alto de la fila es el de la celda más alta.
+    pub fn fila(&mut self, izquierda: Celda, derecha: Celda) -> &mut Self {
+        self.fila_con(izquierda, derecha, &OpcionesFila::default())
+    }
+
+    /// Como `fila`, con sangría o puntos guía.
+    pub fn fila_con(
+        &mut self,
+        izquierda: Celda,
+        derecha: Celda,
+        opciones: &OpcionesFila,
+    ) -> &mut Self {
+        self.y = self.pdf.set_row(
+            &izquierda,
+            &derecha,
+            self.y + self.separacion,
+            opciones,
+        );
+        self
+    }
+
+    /// Ícono de `ancho` x `alto` mm con su borde superior en la Y actual.
+    pub fn imagen(
+        &mut self,
+        x: f32,
+        ancho: f32,
+        alto: f32,
+        icono: &str,
+    ) -> &mut Self {
+        self.pdf.set_img(x, self.y + alto, ancho, alto, icono);
+        self
+    }
+
+    /// Línea de margen a margen en la Y actual.
+    pub fn linea(&mut self, estilo: EstiloLinea) -> &mut Self {
+        self.pdf.set_linea_estilo(self.y, estilo);
+        self
+    }
+
+    /// Separador con ícono (ver `set_separacion`) desde la Y actual.
+    pub fn separador(&mut self, icono: &str) -> &mut Self {
+        self.pdf.set_separacion(self.y, icono);
+        self
+    }
+
+    /// Caja de margen a margen desde `y_inicio` hasta la Y actual.
+    pub fn caja(&mut self, y_inicio: f32, estilo: EstiloCaja) -> &mut Self {
+        self.pdf.set_caja(y_inicio, self.y, estilo);
+        self
+    }
+
+    /// Marca el inicio de una sección en la Y actual (ver `medir`).
+    pub fn seccion(&mut self, nombre: &str) -> &mut Self {
+        self.pdf.marcar_seccion(nombre, self.y);
+        self
+    }
+
+    /// Apila un bloque armado por una función que recibe el PDF y la Y
+    /// inicial y devuelve la Y final, como `set_seccion_impuestos`. No se
+    /// agrega `separacion`: el bloque maneja sus propios espacios.
+    pub fn bloque(
+        &mut self,
+        armar: impl FnOnce(&mut PdfResources<'a>, f32) -> f32,
+    ) -> &mut Self {
+        self.y = armar(self.pdf, self.y);
+        self
+    }
+}
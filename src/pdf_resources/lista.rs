@@ -0,0 +1,284 @@
+use super::figuras::{EstiloCaja, Figura, Forma, Trazo};
+use super::{EstiloTexto, ParagraphData, PdfResources, ANCHO_PAPEL};
+
+/// Gris del texto normal y del invertido (0 = negro, 1 = blanco).
+const GRIS_TEXTO: f32 = 0.0;
+const GRIS_INVERTIDO: f32 = 1.0;
+
+/// Ícono BMP de `assets/img`, con `y` en su borde inferior.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Imagen {
+    pub icono: String,
+    pub x: f32,
+    pub y: f32,
+    pub ancho: f32,
+    pub alto: f32,
+}
+
+/// Lo que se dibuja del ticket, ya ubicado. Las coordenadas van en mm desde
+/// la esquina superior izquierda del papel.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Elemento {
+    /// Texto desde `x` sobre la línea base `y`, con su nivel de gris.
+    Texto {
+        texto: String,
+        light: bool,
+        font_size: f32,
+        x: f32,
+        y: f32,
+        gris: f32,
+    },
+    /// Líneas, cajas y rellenos (fondos del invertido, subrayados,
+    /// tachados y módulos de los códigos de barras).
+    Figura(Figura),
+    Imagen(Imagen),
+}
+
+/// Ticket armado como datos, en el orden en que se dibuja: lo que arma el
+/// layout y consume cada salida (el PDF o el JSON para depurar).
+#[derive(Clone, PartialEq, Debug)]
+pub struct ListaDibujo {
+    pub ancho_mm: f32,
+    pub alto_mm: f32,
+    pub elementos: Vec<Elemento>,
+}
+
+impl PdfResources<'_> {
+    /// Lista de dibujo del ticket: primero las figuras (los rellenos quedan
+    /// detrás), luego los textos con sus decoraciones y al final los íconos
+    /// y, si se pidió `depurar`, las guías del layout.
+    pub fn lista_dibujo(&self) -> ListaDibujo {
+        let mut elementos: Vec<Elemento> =
+            self.figuras.iter().copied().map(Elemento::Figura).collect();
+        for parrafo in &self.paragraphs {
+            agregar_parrafo(&mut elementos, parrafo);
+        }
+        elementos.extend(self.imgs.iter().cloned().map(Elemento::Imagen));
+        if self.depurar {
+            elementos.extend(self.guias_depuracion());
+        }
+        ListaDibujo {
+            ancho_mm: ANCHO_PAPEL,
+            alto_mm: self.page_height,
+            elementos,
+        }
+    }
+}
+
+/// Agrega las líneas del párrafo como textos, palabra por palabra en las
+/// justificadas, junto a la franja de su decoración.
+fn agregar_parrafo(elementos: &mut Vec<Elemento>, parrafo: &ParagraphData) {
+    let mut y_base = parrafo.y_base;
+    for linea in &parrafo.lines {
+        // franja de la decoración, salvo en las líneas en blanco
+        let franja =
+            parrafo
+                .decoracion
+                .filter(|_| !linea.text.is_empty())
+                .map(|d| {
+                    let figura = Figura::relleno(
+                        linea.x_position - d.margen,
+                        y_base - d.arriba,
+                        linea.width_mm + 2.0 * d.margen,
+                        y_base - d.abajo,
+                        GRIS_TEXTO,
+                    );
+                    (d.estilo, Elemento::Figura(figura))
+                });
+        // el fondo del invertido va antes que el texto, en blanco encima
+        let gris = match franja {
+            Some((EstiloTexto::Invertido, _)) => GRIS_INVERTIDO,
+            _ => GRIS_TEXTO,
+        };
+        let texto = |texto: &str, x: f32| Elemento::Texto {
+            texto: texto.to_string(),
+            light: parrafo.light,
+            font_size: parrafo.font_size,
+            x,
+            y: y_base,
+            gris,
+        };
+        let mut textos: Vec<Elemento> = if linea.x_palabras.is_empty() {
+            vec![texto(&linea.text, linea.x_position)]
+        } else {
+            linea
+                .text
+                .split(' ')
+                .zip(&linea.x_palabras)
+                .map(|(palabra, x)| texto(palabra, *x))
+                .collect()
+        };
+        match franja {
+            Some((EstiloTexto::Invertido, fondo)) => {
+                elementos.push(fondo);
+                elementos.append(&mut textos);
+            }
+            // subrayado y tachado van sobre el texto
+            Some((_, raya)) => {
+                elementos.append(&mut textos);
+                elementos.push(raya);
+            }
+            None => elementos.append(&mut textos),
+        }
+        y_base += parrafo.interlinea;
+    }
+}
+
+impl ListaDibujo {
+    /// Exporta la lista como JSON, un elemento por línea para poder
+    /// compararla entre versiones.
+    pub fn json(&self) -> String {
+        let elementos: Vec<String> = self
+            .elementos
+            .iter()
+            .map(|elemento| String::from("    ") + &elemento_json(elemento))
+            .collect();
+        format!(
+            "{{\n  \"ancho_mm\": {},\n  \"alto_mm\": {},\n  \
+             \"elementos\": [\n{}\n  ]\n}}\n",
+            numero(self.ancho_mm),
+            numero(self.alto_mm),
+            elementos.join(",\n")
+        )
+    }
+}
+
+fn elemento_json(elemento: &Elemento) -> String {
+    match elemento {
+        Elemento::Texto {
+            texto,
+            light,
+            font_size,
+            x,
+            y,
+            gris,
+        } => format!(
+            "{{\"tipo\": \"texto\", \"texto\": {}, \"light\": {}, \
+             \"font_size\": {}, \"x\": {}, \"y\": {}, \"gris\": {}}}",
+            cadena(texto),
+            light,
+            numero(*font_size),
+            numero(*x),
+            numero(*y),
+            numero(*gris)
+        ),
+        Elemento::Figura(figura) => {
+            let forma = match figura.forma {
+                Forma::Linea { x1, y1, x2, y2 } => format!(
+                    "\"forma\": \"linea\", \"x1\": {}, \"y1\": {}, \
+                     \"x2\": {}, \"y2\": {}",
+                    numero(x1),
+                    numero(y1),
+                    numero(x2),
+                    numero(y2)
+                ),
+                Forma::Caja {
+                    x,
+                    y_inicio,
+                    ancho,
+                    y_fin,
+                } => format!(
+                    "\"forma\": \"caja\", \"x\": {}, \"y_inicio\": {}, \
+                     \"ancho\": {}, \"y_fin\": {}",
+                    numero(x),
+                    numero(y_inicio),
+                    numero(ancho),
+                    numero(y_fin)
+                ),
+            };
+            format!(
+                "{{\"tipo\": \"figura\", {}, {}}}",
+                forma,
+                estilo_json(&figura.estilo)
+            )
+        }
+        Elemento::Imagen(imagen) => format!(
+            "{{\"tipo\": \"imagen\", \"icono\": {}, \"x\": {}, \"y\": {}, \
+             \"ancho\": {}, \"alto\": {}}}",
+            cadena(&imagen.icono),
+            numero(imagen.x),
+            numero(imagen.y),
+            numero(imagen.ancho),
+            numero(imagen.alto)
+        ),
+    }
+}
+
+fn estilo_json(estilo: &EstiloCaja) -> String {
+    let borde = match estilo.borde {
+        Some(borde) => {
+            let trazo = match borde.trazo {
+                Trazo::Continuo => String::from("\"continuo\""),
+                Trazo::Segmentado { largo, espacio } => format!(
+                    "{{\"largo\": {}, \"espacio\": {}}}",
+                    numero(largo),
+                    numero(espacio)
+                ),
+                Trazo::Punteado => String::from("\"punteado\""),
+            };
+            format!(
+                "{{\"grosor\": {}, \"trazo\": {}, \"tono\": [{}, {}, {}]}}",
+                numero(borde.grosor),
+                trazo,
+                numero(borde.tono.rojo),
+                numero(borde.tono.verde),
+                numero(borde.tono.azul)
+            )
+        }
+        None => String::from("null"),
+    };
+    let relleno = estilo.relleno.map_or(String::from("null"), numero);
+    format!(
+        "\"borde\": {}, \"relleno\": {}, \"radio\": {}",
+        borde,
+        relleno,
+        numero(estilo.radio)
+    )
+}
+
+/// Número con todos sus decimales (el módulo del PDF417 mide 0.216 mm);
+/// JSON no admite NaN ni infinito, que salen como `null`.
+fn numero(valor: f32) -> String {
+    if valor.is_finite() {
+        valor.to_string()
+    } else {
+        String::from("null")
+    }
+}
+
+/// Cadena JSON entre comillas, con los caracteres especiales escapados.
+fn cadena(texto: &str) -> String {
+    let mut json = String::from("\"");
+    for c in texto.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\t' => json += "\\t",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeros_con_precision_y_sin_nan() {
+        assert_eq!(numero(0.216), "0.216");
+        assert_eq!(numero(80.0), "80");
+        assert_eq!(numero(-1.5), "-1.5");
+        assert_eq!(numero(f32::NAN), "null");
+        assert_eq!(numero(f32::INFINITY), "null");
+    }
+
+    #[test]
+    fn cadenas_escapadas() {
+        assert_eq!(cadena("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+        assert_eq!(cadena("\u{1}"), "\"\\u0001\"");
+    }
+}
@@ -0,0 +1,95 @@
+use super::PdfResources;
+
+/// Tramo vertical del ticket ocupado por una sección, en mm desde arriba.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExtensionSeccion {
+    pub nombre: String,
+    pub y_inicio: f32,
+    pub y_fin: f32,
+}
+
+impl ExtensionSeccion {
+    pub fn alto(&self) -> f32 {
+        self.y_fin - self.y_inicio
+    }
+}
+
+/// Largo del ticket y de cada sección, calculado solo con el layout.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MedidaTicket {
+    /// Largo total del papel, en mm: el alto de la página del PDF.
+    pub alto_mm: f32,
+    pub secciones: Vec<ExtensionSeccion>,
+}
+
+impl PdfResources<'_> {
+    /// Marca que desde `y` empieza la sección `nombre`; termina donde empieza
+    /// la siguiente o al final del ticket.
+    pub fn marcar_seccion(&mut self, nombre: &str, y: f32) {
+        self.marcas_seccion.push((nombre.to_string(), y));
+    }
+
+    /// Mide el ticket sin crear el documento: sirve para saber el largo
+    /// antes de imprimir, con el layout ya armado.
+    pub fn medir(&self) -> MedidaTicket {
+        let alto_mm = self.page_height;
+        let secciones = self
+            .marcas_seccion
+            .iter()
+            .enumerate()
+            .map(|(i, (nombre, y_inicio))| {
+                let y_fin = self
+                    .marcas_seccion
+                    .get(i + 1)
+                    .map_or(alto_mm, |(_, siguiente)| *siguiente);
+                ExtensionSeccion {
+                    nombre: nombre.clone(),
+                    y_inicio: *y_inicio,
+                    y_fin,
+                }
+            })
+            .collect();
+        MedidaTicket { alto_mm, secciones }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Caja;
+    use super::*;
+
+    #[test]
+    fn medir_secciones_contiguas_hasta_el_final() {
+        let mut pdf = PdfResources::new();
+        let mut flujo = pdf.flujo(0.0);
+        flujo
+            .seccion("encabezado")
+            .espacio(5.0)
+            .parrafo("La Pizzería", 24.0, Caja::centrada(70.0), false);
+        let fin_encabezado = flujo.y();
+        flujo
+            .seccion("pedidos")
+            .espacio(2.0)
+            .parrafo("1 x Pizza", 16.0, Caja::izquierda(5.0, 70.0), false)
+            .parrafo("2 x Bebida", 16.0, Caja::izquierda(5.0, 70.0), false);
+        let fin = flujo.y();
+
+        let medida = pdf.medir();
+        assert_eq!(medida.alto_mm, fin);
+        let nombres: Vec<&str> =
+            medida.secciones.iter().map(|s| s.nombre.as_str()).collect();
+        assert_eq!(nombres, vec!["encabezado", "pedidos"]);
+        assert_eq!(medida.secciones[0].y_inicio, 0.0);
+        assert_eq!(medida.secciones[0].alto(), fin_encabezado);
+        assert_eq!(medida.secciones[1].y_inicio, fin_encabezado);
+        assert_eq!(medida.secciones[1].y_fin, fin);
+    }
+
+    #[test]
+    fn medir_sin_secciones() {
+        let pdf = PdfResources::new();
+        let medida = pdf.medir();
+        assert_eq!(medida.alto_mm, 0.0);
+        assert!(medida.secciones.is_empty());
+    }
+}
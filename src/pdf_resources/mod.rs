@@ -1,6 +1,5 @@
 extern crate printpdf;
 use chrono::{DateTime};
-use printpdf::path::{PaintMode, WindingOrder};
 
 use std::fs::File;
 use std::io::BufWriter;
@@ -8,24 +7,144 @@ use std::collections::HashMap;
 use printpdf::*;
 use ttf_parser::Face;
 
-// const PAGE_WIDTH: f32 = 80.0;
+mod ajuste;
+mod caja;
+mod depuracion;
+mod figuras;
+mod flujo;
+mod lista;
+mod medida;
+mod silabas;
+mod tabla;
+pub use ajuste::AjusteTexto;
+use ajuste::cortar_con_elipsis;
+pub use caja::{Alineacion, Caja};
+pub use figuras::{EstiloCaja, EstiloLinea};
+pub use medida::MedidaTicket;
+pub use tabla::{AnchoColumna, Columna, Tabla};
+
+use caja::alinear_lineas;
+use figuras::Figura;
+use lista::{Elemento, Imagen};
+use silabas::cortes_silaba;
+
+/// Ancho del papel térmico, en mm.
+const ANCHO_PAPEL: f32 = 80.0;
+/// Bordes izquierdo y derecho de la zona imprimible, en mm.
+const MARGEN_IZQUIERDO: f32 = 5.0;
+const MARGEN_DERECHO: f32 = ANCHO_PAPEL - MARGEN_IZQUIERDO;
 const DPI: f32 = 300.0;
+/// Milímetros por punto tipográfico.
+const PT_A_MM: f32 = 0.352778;
+/// Lo que sobresale el fondo del texto invertido alrededor del texto, en mm.
+const MARGEN_INVERTIDO: f32 = 1.0;
+/// Grosor mínimo de subrayados y tachados para que la impresora térmica
+/// no los pierda, en mm.
+const GROSOR_MINIMO_MM: f32 = 0.25;
+
+/// Estilo con el que se dibuja un párrafo.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EstiloTexto {
+    #[default]
+    Normal,
+    /// Texto blanco sobre un recuadro negro.
+    Invertido,
+    /// Texto con una línea que lo cruza (p.ej. ítems eliminados).
+    Tachado,
+    /// Texto con una línea debajo (p.ej. títulos de sección).
+    Subrayado,
+}
+
+/// Franja con la que se decora cada línea de un párrafo: el fondo del
+/// texto invertido, el subrayado o el tachado. Se calcula al armar el
+/// párrafo, con las métricas de la fuente.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Decoracion {
+    pub estilo: EstiloTexto,
+    /// Bordes de la franja respecto de la línea base, en mm (positivo hacia
+    /// arriba).
+    pub abajo: f32,
+    pub arriba: f32,
+    /// Lo que sobresale la franja a cada lado del texto, en mm.
+    pub margen: f32,
+}
+
+/// Texto de una celda de fila.
+pub struct Celda<'t> {
+    pub texto: &'t str,
+    pub font_size: f32,
+    pub light: bool,
+    pub estilo: EstiloTexto,
+}
+
+impl<'t> Celda<'t> {
+    pub fn bold(texto: &'t str, font_size: f32) -> Self {
+        Celda {
+            texto,
+            font_size,
+            light: false,
+            estilo: EstiloTexto::Normal,
+        }
+    }
+
+    pub fn light(texto: &'t str, font_size: f32) -> Self {
+        Celda {
+            texto,
+            font_size,
+            light: true,
+            estilo: EstiloTexto::Normal,
+        }
+    }
+
+    /// La misma celda dibujada con `estilo`.
+    pub fn con_estilo(self, estilo: EstiloTexto) -> Self {
+        Celda { estilo, ..self }
+    }
+}
+
+/// Opciones de `set_row`.
+#[derive(Default)]
+pub struct OpcionesFila {
+    /// Sangría de la celda izquierda desde el margen, en mm.
+    pub sangria: f32,
+    /// Puntos guía entre la celda izquierda y la derecha.
+    pub guias: bool,
+    /// Ancho máximo de la celda derecha, en mm; lo que no entra se corta
+    /// con "…". Sin él, la celda derecha puede ocupar toda la fila.
+    pub ancho_derecha: Option<f32>,
+}
 
 pub struct ParagraphData {
     pub lines: Vec<ParrafoLine>,
     pub font_size: f32,
     pub light: bool,
-    /// Si quieres, también puedes guardar el `final_y` por si te sirve para
-    /// cálculos posteriores (p.ej. para saber dónde empieza el siguiente párrafo).
-    ///
-    pub y_inicial: f32,
+    /// Decoración de cada línea según el estilo, si no es `Normal`.
+    pub decoracion: Option<Decoracion>,
+    /// Caja donde se acomodó el texto; `None` en los puntos guía de las
+    /// filas.
+    pub caja: Option<Caja>,
+    /// Borde superior de la primera línea, en mm desde el borde superior de
+    /// la página.
+    pub y_inicio: f32,
+    /// Línea base de la primera línea, en mm desde el borde superior de la
+    /// página.
+    pub y_base: f32,
+    /// Distancia entre líneas base, en mm.
+    pub interlinea: f32,
     // pub final_y: f32,
 }
 
+#[derive(Default)]
 pub struct ParrafoLine {
     pub text: String,
-    // pub width_mm: f32,
+    pub width_mm: f32,
     pub x_position: f32,
+    /// X de cada palabra en las líneas justificadas; si está vacío la línea
+    /// se dibuja entera desde `x_position`.
+    pub x_palabras: Vec<f32>,
+    /// Última línea de un renglón del texto (antes de un salto de línea o
+    /// al final), que no se justifica.
+    pub fin_renglon: bool,
 }
 
 pub struct FontData<'a> {
@@ -33,11 +152,100 @@ pub struct FontData<'a> {
     pub upem: f32,
     pub face: Face<'a>,
 }
-pub struct ImagePreMake {
-    pub img: Image,
-    pub trans: Box<dyn Fn(f32) -> ImageTransform>,
-}
 
+/// Métricas verticales de la fuente. Se usan las tipográficas (OS/2) que
+/// definen el interlineado de diseño; las `hhea` de Segoe UI reservan
+/// espacio para diacríticos apilados y separan demasiado las líneas.
+impl FontData<'_> {
+    fn unidades_a_mm(&self, unidades: i16, font_size: f32) -> f32 {
+        unidades as f32 / self.upem * font_size * PT_A_MM
+    }
+
+    /// Alto del ascendente de la fuente sobre la línea base, en mm.
+    fn ascenso_mm(&self, font_size: f32) -> f32 {
+        let ascender = self
+            .face
+            .typographic_ascender()
+            .unwrap_or(self.face.ascender());
+        self.unidades_a_mm(ascender, font_size)
+    }
+
+    /// Alto del descendente bajo la línea base, en mm.
+    fn descenso_mm(&self, font_size: f32) -> f32 {
+        let descender = self
+            .face
+            .typographic_descender()
+            .unwrap_or(self.face.descender());
+        -self.unidades_a_mm(descender, font_size)
+    }
+
+    /// Espacio entre líneas que agrega la fuente, en mm.
+    fn espacio_lineas_mm(&self, font_size: f32) -> f32 {
+        let line_gap = self
+            .face
+            .typographic_line_gap()
+            .unwrap_or(self.face.line_gap());
+        self.unidades_a_mm(line_gap, font_size)
+    }
+
+    /// Alto de una línea: ascendente + descendente + espacio entre líneas.
+    fn alto_linea_mm(&self, font_size: f32) -> f32 {
+        self.ascenso_mm(font_size)
+            + self.descenso_mm(font_size)
+            + self.espacio_lineas_mm(font_size)
+    }
+
+    /// Distancia del borde superior de la línea a su línea base; el espacio
+    /// entre líneas se reparte mitad arriba y mitad abajo.
+    fn base_mm(&self, font_size: f32) -> f32 {
+        self.espacio_lineas_mm(font_size) / 2.0 + self.ascenso_mm(font_size)
+    }
+
+    /// Decoración de las líneas con `estilo`: el fondo invertido cubre del
+    /// descendente al ascendente; subrayado y tachado usan la posición y el
+    /// grosor que define la fuente.
+    fn decoracion(
+        &self,
+        estilo: EstiloTexto,
+        font_size: f32,
+    ) -> Option<Decoracion> {
+        let linea = match estilo {
+            EstiloTexto::Normal => return None,
+            EstiloTexto::Invertido => {
+                return Some(Decoracion {
+                    estilo,
+                    abajo: -self.descenso_mm(font_size) - MARGEN_INVERTIDO,
+                    arriba: self.ascenso_mm(font_size) + MARGEN_INVERTIDO,
+                    margen: MARGEN_INVERTIDO,
+                });
+            }
+            EstiloTexto::Tachado => self.face.strikeout_metrics(),
+            EstiloTexto::Subrayado => self.face.underline_metrics(),
+        };
+        // sin métricas en la fuente: a media altura de la x o bajo la base
+        let (posicion, grosor) = match linea {
+            Some(linea) => (
+                self.unidades_a_mm(linea.position, font_size),
+                self.unidades_a_mm(linea.thickness, font_size),
+            ),
+            None => {
+                let em = font_size * PT_A_MM;
+                let posicion = match estilo {
+                    EstiloTexto::Tachado => 0.25 * em,
+                    _ => -0.1 * em,
+                };
+                (posicion, 0.05 * em)
+            }
+        };
+        let grosor = grosor.max(GROSOR_MINIMO_MM);
+        Some(Decoracion {
+            estilo,
+            abajo: posicion - grosor / 2.0,
+            arriba: posicion + grosor / 2.0,
+            margen: 0.0,
+        })
+    }
+}
 pub struct CurrentPdf {
     pub doc: PdfDocumentReference,
     // pub page: PdfPageIndex,
@@ -49,10 +257,19 @@ pub struct PdfResources<'a> {
     pub bold: FontData<'a>,
     pub light: FontData<'a>,
     pub paragraphs: Vec<ParagraphData>,
-    pub polygons: Vec<Box<dyn Fn(f32) -> Polygon>>,
-    pub imgs: Vec<ImagePreMake>,
+    /// Líneas, cajas y rellenos; se dibujan antes que el texto para que los
+    /// rellenos queden detrás.
+    pub figuras: Vec<Figura>,
+    /// Inicio de cada sección marcada con `marcar_seccion`, en orden.
+    pub marcas_seccion: Vec<(String, f32)>,
+    pub imgs: Vec<Imagen>,
     pub current_pdf: Option<CurrentPdf>,
     pub page_height: f32,
+    /// Multiplicador del alto de línea de la fuente (1.0 = el de la fuente).
+    pub interlineado: f32,
+    /// Dibuja encima del ticket las guías del layout (cajas, líneas base,
+    /// íconos y una regla en mm).
+    pub depurar: bool,
 }
 
 impl<'a> PdfResources<'a> {
@@ -85,12 +302,17 @@ impl<'a> PdfResources<'a> {
             bold,
             light,
             paragraphs: Vec::new(),
-            polygons: Vec::new(),
+            figuras: Vec::new(),
+            marcas_seccion: Vec::new(),
             imgs: Vec::new(),
             current_pdf: None,
             page_height: 0.0,
+            interlineado: 1.0,
+            depurar: false,
         }
     }
+    /// Agrega el ícono `icono` de `mm_x` x `mm_y`, con su esquina inferior
+    /// izquierda en (`x`, `y`).
     pub fn set_img(
         &mut self,
         x: f32,
@@ -99,66 +321,51 @@ impl<'a> PdfResources<'a> {
         mm_y: f32,
         icono: &str,
     ) {
-        fn px_to_mm(px: f32) -> f32 {
-            // There are 25.4 millimeters in an inch.
-            let mm = (px / DPI) * 25.4;
-            mm as f32
-        }
-
-        let path_icono = "assets/img/".to_owned() + icono + ".bmp";
-
-        let mut image_file = File::open(path_icono).unwrap();
-        let img: Image = Image::try_from(
-            image_crate::codecs::bmp::BmpDecoder::new(&mut image_file).unwrap(),
-        )
-        .unwrap();
-
-        let base_scale_x = px_to_mm(img.image.width.0 as f32);
-        let base_scale_y = px_to_mm(img.image.height.0 as f32);
-
-        // println!("{}[px] X {}[px]", img.image.width.0, img.image.height.0);
-        // println!(" {}[mm] x {}[mm]", base_scale_x, base_scale_y,);
-        // scale_x * base_scale_x = mm_x
-        let scale_x = mm_x / base_scale_x;
-        let scale_y = mm_y / base_scale_y;
-
-        let image_transform: Box<dyn Fn(f32) -> ImageTransform> =
-            Box::new(move |height: f32| {
-                ImageTransform {
-                    translate_x: Some(Mm(x)),
-                    translate_y: Some(Mm(height - y)),
-                    rotate: None,
-                    scale_x: Some(scale_x), // Usa los valores capturados
-                    scale_y: Some(scale_y), // Usa los valores capturados
-                    dpi: Some(DPI),
-                }
-            });
-        let img_save = ImagePreMake {
-            img: img,
-            trans: image_transform,
-        };
-
-        self.imgs.push(img_save);
+        self.imgs.push(Imagen {
+            icono: icono.to_string(),
+            x,
+            y,
+            ancho: mm_x,
+            alto: mm_y,
+        });
     }
 
     pub fn set_rect(&mut self, y_inicio: f32, y_fin: f32) {
-        self.polygons.push(set_linea_horizontal(y_inicio));
-        self.polygons.push(set_linea_horizontal(y_fin));
-
-        let lines_ver = set_linea_vertical(y_inicio, y_fin);
-        self.polygons.push(lines_ver.0);
-        self.polygons.push(lines_ver.1);
+        self.set_caja(y_inicio, y_fin, EstiloCaja::default());
     }
 
     /// Crea el layout de un párrafo y lo guarda internamente en `self.paragraphs`.
+    ///
+    /// El texto se parte y se alinea dentro de `caja`. `y_inicial` es el
+    /// borde superior de la primera línea; devuelve el borde inferior de la
+    /// última.
     pub fn set_paragraph(
         &mut self,
         text: &str,
         font_size: f32,
         y_inicial: f32,
-        max_width_mm: f32,
-        tipo: i8,
+        caja: Caja,
+        light: bool,
+    ) -> f32 {
+        self.set_paragraph_estilo(
+            text,
+            font_size,
+            y_inicial,
+            caja,
+            light,
+            EstiloTexto::Normal,
+        )
+    }
+
+    /// Igual que `set_paragraph`, pero dibujando el texto con `estilo`.
+    pub fn set_paragraph_estilo(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        y_inicial: f32,
+        caja: Caja,
         light: bool,
+        estilo: EstiloTexto,
     ) -> f32 {
         let font_data: &FontData<'a> = if light {
             &self.light
@@ -167,77 +374,263 @@ impl<'a> PdfResources<'a> {
         };
 
         // Llamamos a tu función de layout
-        let (lines, final_y) = layout_parrafo(
-            text,
-            font_size,
-            y_inicial,
-            max_width_mm,
-            tipo,
-            font_data,
-        );
+        let lines = layout_parrafo(text, font_size, &caja, font_data);
 
-        // Guardamos el resultado en nuestro vector
-        let paragraph_data = ParagraphData {
-            lines,
-            y_inicial,
+        // Guardamos el resultado y devolvemos el final_y para que el que
+        // llame sepa dónde quedó la última línea
+        let celda = Celda {
+            texto: text,
             font_size,
             light,
-            // final_y,
+            estilo,
         };
+        self.agregar_lineas(lines, &celda, Some(caja), y_inicial)
+    }
+    /// Fila con un texto a la izquierda y otro a la derecha (p.ej. nombre
+    /// y precio de un ítem). Primero se mide la celda derecha y la izquierda
+    /// se parte en el ancho que queda, para que nunca pase por debajo.
+    ///
+    /// Devuelve la posición Y donde termina la fila.
+    pub fn set_row(
+        &mut self,
+        izquierda: &Celda,
+        derecha: &Celda,
+        y_inicial: f32,
+        opciones: &OpcionesFila,
+    ) -> f32 {
+        // espacio mínimo entre celdas
+        let hueco = 2.0;
+        let x_izquierda = MARGEN_IZQUIERDO + opciones.sangria;
+
+        // ambas celdas comparten la línea base aunque tengan otro tamaño
+        let ascenso_izquierda =
+            self.fuente(izquierda.light).base_mm(izquierda.font_size);
+        let ascenso_derecha =
+            self.fuente(derecha.light).base_mm(derecha.font_size);
+        let ascenso = ascenso_izquierda.max(ascenso_derecha);
+        let y_izquierda = y_inicial + ascenso - ascenso_izquierda;
+        let y_derecha = y_inicial + ascenso - ascenso_derecha;
+
+        let mut y_final = y_inicial;
+        let mut fin_derecha = MARGEN_DERECHO;
+        if !derecha.texto.is_empty() {
+            let font_derecha = self.fuente(derecha.light);
+            let ancho_max = opciones
+                .ancho_derecha
+                .unwrap_or(MARGEN_DERECHO - x_izquierda);
+            // la celda derecha va en una sola línea, cortada si no entra
+            let mut lineas = vec![ParrafoLine {
+                text: derecha.texto.to_string(),
+                width_mm: medir_texto_mm(
+                    derecha.texto,
+                    derecha.font_size,
+                    font_derecha,
+                ),
+                ..ParrafoLine::default()
+            }];
+            if lineas[0].width_mm > ancho_max {
+                cortar_con_elipsis(
+                    &mut lineas,
+                    1,
+                    ancho_max,
+                    derecha.font_size,
+                    font_derecha,
+                );
+            }
+            let mut linea = lineas.remove(0);
+            let ancho = linea.width_mm;
+            fin_derecha = MARGEN_DERECHO - ancho;
+            linea.x_position = fin_derecha;
+            y_final = self.agregar_lineas(
+                vec![linea],
+                derecha,
+                Some(Caja::derecha(fin_derecha, ancho)),
+                y_derecha,
+            );
+        }
+
+        let ancho_izquierda = (fin_derecha - hueco - x_izquierda).max(0.0);
+        let font_izquierda = self.fuente(izquierda.light);
+        let mut lineas = partir_lineas(
+            izquierda.texto,
+            izquierda.font_size,
+            ancho_izquierda,
+            font_izquierda,
+        );
+        for linea in &mut lineas {
+            linea.x_position = x_izquierda;
+        }
+
+        // puntos guía entre el final de la primera línea y la celda derecha
+        if opciones.guias && !derecha.texto.is_empty() {
+            let fin_texto = lineas
+                .first()
+                .map_or(x_izquierda, |linea| x_izquierda + linea.width_mm);
+            let punto = medir_texto_mm(".", izquierda.font_size, &self.light);
+            let espacio = fin_derecha - fin_texto - 2.0 * hueco;
+            if punto > 0.0 && espacio > punto {
+                let puntos = (espacio / punto).floor() as usize;
+                let guia = ParrafoLine {
+                    text: ".".repeat(puntos),
+                    width_mm: puntos as f32 * punto,
+                    x_position: fin_derecha - hueco - puntos as f32 * punto,
+                    ..ParrafoLine::default()
+                };
+                let celda_guia = Celda::light("", izquierda.font_size);
+                self.agregar_lineas(
+                    vec![guia],
+                    &celda_guia,
+                    None,
+                    y_izquierda,
+                );
+            }
+        }
+
+        let caja_izquierda = Caja::izquierda(x_izquierda, ancho_izquierda);
+        let y_final_izquierda = self.agregar_lineas(
+            lineas,
+            izquierda,
+            Some(caja_izquierda),
+            y_izquierda,
+        );
+        y_final.max(y_final_izquierda)
+    }
+
+    fn fuente(&self, light: bool) -> &FontData<'a> {
+        if light {
+            &self.light
+        } else {
+            &self.bold
+        }
+    }
+
+    /// Guarda líneas ya posicionadas como un párrafo cuyo borde superior
+    /// está en `y_inicial`, con la fuente y el estilo de `celda`, y devuelve
+    /// su borde inferior.
+    fn agregar_lineas(
+        &mut self,
+        lines: Vec<ParrafoLine>,
+        celda: &Celda,
+        caja: Option<Caja>,
+        y_inicial: f32,
+    ) -> f32 {
+        let font_data = self.fuente(celda.light);
+        let y_base = y_inicial + font_data.base_mm(celda.font_size);
+        let interlinea =
+            font_data.alto_linea_mm(celda.font_size) * self.interlineado;
+        let final_y = y_inicial + lines.len() as f32 * interlinea;
+        self.paragraphs.push(ParagraphData {
+            lines,
+            caja,
+            y_inicio: y_inicial,
+            y_base,
+            interlinea,
+            font_size: celda.font_size,
+            light: celda.light,
+            decoracion: font_data.decoracion(celda.estilo, celda.font_size),
+        });
+        if final_y > self.page_height {
+            self.page_height = final_y;
+        }
+        final_y
+    }
 
-        self.paragraphs.push(paragraph_data);
+    /// Agrega un código de barras 2D (p.ej. PDF417) con un rectángulo
+    /// relleno por cada tramo de módulos negros.
+    ///
+    /// Devuelve la posición Y donde termina el código.
+    pub fn set_codigo_barras(
+        &mut self,
+        x: f32,
+        y: f32,
+        ancho_modulo: f32,
+        alto_fila: f32,
+        modulos: Vec<Vec<bool>>,
+    ) -> f32 {
+        let final_y = y + modulos.len() as f32 * alto_fila;
+        for (fila, modulos_fila) in modulos.iter().enumerate() {
+            let arriba = y + fila as f32 * alto_fila;
+            let mut col = 0;
+            while col < modulos_fila.len() {
+                if !modulos_fila[col] {
+                    col += 1;
+                    continue;
+                }
+                let inicio = col;
+                while col < modulos_fila.len() && modulos_fila[col] {
+                    col += 1;
+                }
+                self.figuras.push(Figura::relleno(
+                    x + inicio as f32 * ancho_modulo,
+                    arriba,
+                    (col - inicio) as f32 * ancho_modulo,
+                    arriba + alto_fila,
+                    0.0,
+                ));
+            }
+        }
 
         if final_y > self.page_height {
             self.page_height = final_y;
         }
-        // Podemos devolver el final_y para que el que llame sepa
-        // dónde quedó la última línea (por si quieres encadenar párrafos).
         final_y
     }
     pub fn set_linea(&mut self, y: f32) {
-        self.polygons.push(set_linea_horizontal(y));
+        self.set_linea_estilo(y, EstiloLinea::default());
     }
     pub fn set_separacion(&mut self, y: f32, icono: &str) {
-        self.polygons.push(set_linea_horizontal(y + 1.5));
-        self.polygons.push(set_linea_horizontal(y + 2.75));
+        self.set_linea(y + 1.5);
+        self.set_linea(y + 2.75);
         self.set_img(37.0, y +  5.5, 6.0, 6.0, icono);
     }
-    /// Dibuja las líneas en el PDF usando el `current_layer`.
-    /// Requiere los datos de las líneas previamente calculadas por `layout_parrafo`.
-    pub fn draw_parrafo(
-        current_layer: &PdfLayerReference,
-        lines: &[ParrafoLine],
-        y_inicial: f32,
-        font_size: f32,
-        font: &IndirectFontRef,
+    /// Dibuja un elemento de la lista en el PDF usando el `current_layer`.
+    fn draw_elemento(
+        use_pdf: &CurrentPdf,
+        elemento: &Elemento,
+        page_height: f32,
     ) {
-        // Devolvemos la última posición X e Y usada
-        // 3. Dibujamos línea a línea
-        let mut y_position = y_inicial;
-        // let mut last_x_position = 10.0; // o como gustes
-
-        // 2. Interlineado
-        let line_spacing = font_size * 0.4;
-        for line_info in lines {
-            // Dibujar la línea
-            current_layer.use_text(
-                &line_info.text,
+        let current_layer = &use_pdf.current_layer;
+        match elemento {
+            Elemento::Texto {
+                texto,
+                light,
                 font_size,
-                Mm(line_info.x_position),
-                Mm(y_position),
-                font,
-            );
-
-            // last_x_position = line_info.x_position;
-            // Para la siguiente línea, reducimos Y
-            y_position -= line_spacing;
+                x,
+                y,
+                gris,
+            } => {
+                let font = if *light {
+                    &use_pdf.font_light
+                } else {
+                    &use_pdf.font_bold
+                };
+                if *gris != 0.0 {
+                    current_layer.set_fill_color(tinta(*gris));
+                }
+                current_layer.use_text(
+                    texto,
+                    *font_size,
+                    Mm(*x),
+                    Mm(page_height - y),
+                    font,
+                );
+                if *gris != 0.0 {
+                    current_layer.set_fill_color(negro());
+                }
+            }
+            Elemento::Figura(figura) => {
+                Self::draw_figura(current_layer, figura, page_height);
+            }
+            Elemento::Imagen(imagen) => {
+                draw_imagen(current_layer, imagen, page_height);
+            }
         }
     }
 
     pub fn init_draw(&mut self) {
         let (doc, page, layer) = PdfDocument::new(
             "Ticket",
-            Mm(80.0),
+            Mm(ANCHO_PAPEL),
             Mm(self.page_height),
             "",
         );
@@ -263,44 +656,12 @@ impl<'a> PdfResources<'a> {
     }
 
     pub fn drow_all_obj(&mut self) {
+        let lista = self.lista_dibujo();
         match &self.current_pdf {
             Some(use_pdf) => {
-                // textos
-                match &self.current_pdf {
-                    Some(use_pdf) => {
-                        for p in &self.paragraphs {
-                            let font_use: &IndirectFontRef = if p.light {
-                                &use_pdf.font_light
-                            } else {
-                                &use_pdf.font_bold
-                            };
-                            Self::draw_parrafo(
-                                &use_pdf.current_layer,
-                                &p.lines,
-                                self.page_height - p.y_inicial,
-                                p.font_size,
-                                font_use,
-                            );
-                        }
-                    }
-                    None => {
-                        print!("Error");
-                    }
-                };
-                
-                // plogonos
-                for poly in &self.polygons {
-                    use_pdf.current_layer.add_polygon((poly)(self.page_height));
-                }
-                // imagenes
-
-                for img in self.imgs.drain(..) {
-                    img.img.add_to_layer(
-                        use_pdf.current_layer.clone(),
-                        (img.trans)(self.page_height),
-                    );
+                for elemento in &lista.elementos {
+                    Self::draw_elemento(use_pdf, elemento, lista.alto_mm);
                 }
-
             }
             None => {
                 print!("Error");
@@ -308,11 +669,11 @@ impl<'a> PdfResources<'a> {
         };
     }
 
-    pub fn save_pdf(self) {
+    pub fn save_pdf(self, ruta: &str) {
         match self.current_pdf {
             Some(use_pdf) => {
                 let mut buffer: BufWriter<File> =
-                    BufWriter::new(File::create("test_working.pdf").unwrap());
+                    BufWriter::new(File::create(ruta).unwrap());
                 use_pdf.doc.save(&mut buffer).unwrap();
             }
             None => {
@@ -322,126 +683,156 @@ impl<'a> PdfResources<'a> {
     }
 }
 
-/// Calcula la disposición (layout) de las líneas de un párrafo,
-/// sin dibujar nada todavía.
-///
-/// `tipo` define la alineación:
-///   - < 0 => izquierda
-///   - = 0 => centrada
-///   - > 0 => derecha
+/// Ancho en mm de `texto` dibujado a `font_size` puntos.
+fn medir_texto_mm(texto: &str, font_size: f32, font_data: &FontData) -> f32 {
+    let scale_factor = font_size / font_data.upem;
+    let width_points: f32 = texto
+        .chars()
+        .filter_map(|c| font_data.face.glyph_index(c))
+        .map(|glyph_id| {
+            font_data.face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32
+        })
+        .sum::<f32>()
+        * scale_factor;
+
+    // Convertir de puntos tipográficos a mm
+    width_points * PT_A_MM
+}
+
+/// Parte el texto en líneas de a lo más `max_width_mm` de ancho. Las líneas
+/// quedan con `x_position` en 0; la alineación la decide quien llama.
 ///
-/// Devuelve:
-///   - `Vec<ParrafoLine>`: cada línea con su texto, ancho y X calculado
-///   - `final_y`: la posición Y que queda tras colocar todas las líneas
-fn layout_parrafo(
+/// Los saltos de línea del texto se respetan. Una palabra que no cabe en lo
+/// que queda de la línea se corta con guion entre sílabas, y si no hay
+/// sílaba que quepa (una URL, un correo) se corta donde llegue el ancho.
+fn partir_lineas(
     text: &str,
     font_size: f32,
-    y_inicial: f32,
     max_width_mm: f32,
-    tipo: i8,
     font_data: &FontData,
-) -> (Vec<ParrafoLine>, f32) {
-    // 2. Calcular factor de escala de la fuente
-    let scale_factor = font_size / font_data.upem;
-
-    // 3. Dividir el texto en palabras
-    let words: Vec<&str> = text.split_whitespace().collect();
-
-    // 4. Función para medir el ancho de una palabra en mm
-    let measure_word_mm = |w: &str| -> f32 {
-        let width_points: f32 = w
-            .chars()
-            .filter_map(|c| font_data.face.glyph_index(c))
-            .map(|glyph_id| {
-                font_data.face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32
-            })
-            .sum::<f32>()
-            * scale_factor;
-
-        // Convertir de puntos tipográficos a mm (aprox)
-        width_points * 0.352778
-    };
-
-    // 5. Recorremos las palabras para construir líneas
-    let mut lines_text: Vec<String> = Vec::new();
-    let mut current_line = String::new();
-    let mut current_line_width: f32 = 0.0;
-
+) -> Vec<ParrafoLine> {
+    let measure_word_mm =
+        |w: &str| -> f32 { medir_texto_mm(w, font_size, font_data) };
     let space_width_mm = measure_word_mm(" "); // ancho de un espacio
+    let guion_width_mm = measure_word_mm("-");
+
+    // cada línea con su indicador de fin de renglón
+    let mut lines_text: Vec<(String, bool)> = Vec::new();
+    // cada renglón del texto empieza en una línea nueva
+    for renglon in text.lines() {
+        let mut current_line = String::new();
+        let mut current_line_width: f32 = 0.0;
+
+        for word in renglon.split_whitespace() {
+            // lo que falta por colocar de la palabra
+            let mut resto = word;
+            loop {
+                // Si la línea actual está vacía no agregamos espacio
+                let extra_width = if current_line.is_empty() {
+                    0.0
+                } else {
+                    space_width_mm
+                };
+                let word_width = measure_word_mm(resto);
+
+                // Cabe entera
+                if current_line_width + extra_width + word_width
+                    <= max_width_mm
+                {
+                    if !current_line.is_empty() {
+                        current_line.push(' ');
+                    }
+                    current_line.push_str(resto);
+                    current_line_width += extra_width + word_width;
+                    break;
+                }
 
-    for (i, word) in words.iter().enumerate() {
-        // Ancho de la palabra actual
-        let word_width = measure_word_mm(word);
-
-        // Si la línea actual está vacía no agregamos espacio
-        let sep = if i == 0 || current_line.is_empty() {
-            ""
-        } else {
-            " "
-        };
-        let extra_width = if sep.is_empty() {
-            0.0
-        } else {
-            space_width_mm
-        };
-
-        // Ver si cabe en la línea actual
-        if current_line_width + word_width + extra_width <= max_width_mm {
-            // Cabe
-            if current_line.is_empty() {
-                current_line = word.to_string();
-            } else {
-                current_line.push_str(sep);
-                current_line.push_str(word);
+                // No cabe: la sílaba más larga que quepa con su guion
+                let disponible = max_width_mm
+                    - current_line_width
+                    - extra_width
+                    - guion_width_mm;
+                let corte = cortes_silaba(resto).into_iter().rev().find(
+                    |&corte| measure_word_mm(&resto[..corte]) <= disponible,
+                );
+                if let Some(corte) = corte {
+                    if !current_line.is_empty() {
+                        current_line.push(' ');
+                    }
+                    current_line.push_str(&resto[..corte]);
+                    current_line.push('-');
+                    lines_text.push((std::mem::take(&mut current_line), false));
+                    current_line_width = 0.0;
+                    resto = &resto[corte..];
+                } else if !current_line.is_empty() {
+                    // probamos de nuevo en una línea vacía
+                    lines_text.push((std::mem::take(&mut current_line), false));
+                    current_line_width = 0.0;
+                } else {
+                    // ni sola ni con guion: se corta por caracteres, al
+                    // menos uno por línea para avanzar siempre
+                    let corte = resto
+                        .char_indices()
+                        .map(|(i, c)| i + c.len_utf8())
+                        .take_while(|&fin| {
+                            measure_word_mm(&resto[..fin]) <= max_width_mm
+                        })
+                        .last()
+                        .unwrap_or_else(|| {
+                            resto.chars().next().map_or(0, char::len_utf8)
+                        });
+                    lines_text.push((resto[..corte].to_string(), false));
+                    resto = &resto[corte..];
+                }
             }
-            current_line_width += word_width + extra_width;
-        } else {
-            // No cabe: guardamos la línea y empezamos una nueva
-            lines_text.push(current_line);
-            current_line = word.to_string();
-            current_line_width = word_width;
         }
+        // un renglón vacío queda como línea en blanco
+        lines_text.push((current_line, true));
     }
-    // Agregamos la última si quedó algo
-    if !current_line.is_empty() {
-        lines_text.push(current_line);
-    }
-
-    // 6. Para cada línea calculamos su ancho y su X
-    let mut lines_layout = Vec::new();
-    for line_str in &lines_text {
-        let line_width_mm = measure_word_mm(line_str);
-
-        let x_position = if tipo < 0 {
-            // Izquierda
-            -5.0 * tipo as f32
-        } else if tipo == 0 {
-            // Centrado en la página
-            (80.0 - line_width_mm) / 2.0
-        } else {
-            // Derecha (asume contenedor de `max_width_mm` empezando en x=10)
-            // Podrías ajustarlo a tus márgenes reales
-            5.0 * tipo as f32 + (max_width_mm - line_width_mm)
-        };
-
-        let line_info = ParrafoLine {
-            text: line_str.to_string(),
-            // width_mm: line_width_mm,
-            x_position,
-        };
 
-        lines_layout.push(line_info);
-    }
+    lines_text
+        .into_iter()
+        .map(|(line_str, fin_renglon)| ParrafoLine {
+            width_mm: measure_word_mm(&line_str),
+            text: line_str,
+            fin_renglon,
+            ..ParrafoLine::default()
+        })
+        .collect()
+}
 
-    // 7. Calculamos la posición Y “final”, asumiendo un interlineado
-    //    (Aunque podrías no retornarlo y que se maneje afuera).
-    let line_spacing = font_size * 0.4;
-    let final_y = y_inicial + (lines_layout.len() as f32 * line_spacing);
+/// Calcula la disposición (layout) de las líneas de un párrafo,
+/// sin dibujar nada todavía: lo parte al ancho de texto de `caja` y ubica
+/// cada línea según su alineación.
+///
+/// La posición Y la asigna `agregar_lineas` con las métricas de la fuente.
+fn layout_parrafo(
+    text: &str,
+    font_size: f32,
+    caja: &Caja,
+    font_data: &FontData,
+) -> Vec<ParrafoLine> {
+    let mut lines_layout =
+        partir_lineas(text, font_size, caja.ancho_texto(), font_data);
+    alinear_lineas(&mut lines_layout, caja, font_size, font_data);
+    lines_layout
+}
 
-    (lines_layout, final_y)
+/// Ruta del ícono `icono` (nombre sin extensión) dentro de `assets/img`.
+fn ruta_icono(icono: &str) -> String {
+    "assets/img/".to_owned() + icono + ".bmp"
 }
 
+/// Indica si el ícono está instalado.
+fn existe_icono(icono: &str) -> bool {
+    std::path::Path::new(&ruta_icono(icono)).exists()
+}
 
+/// Logo a imprimir, solo si está instalado en `assets/img`: los logos de
+/// plataformas y couriers no vienen en el repositorio.
+pub fn logo_opcional(logo: Option<&str>) -> Option<&str> {
+    logo.filter(|logo| existe_icono(logo))
+}
 
 pub fn format_datetime(iso_date: &str) -> (String, String) {
     // Parseamos la fecha ISO
@@ -478,93 +869,46 @@ pub fn format_datetime(iso_date: &str) -> (String, String) {
 }
 
 
-pub fn format_clp(amount: i32) -> String {
-    let mut formatted = String::new();
-    let amount_str = amount.to_string();
-    let mut count = 0;
-
-    for ch in amount_str.chars().rev() {
-        if count > 0 && count % 3 == 0 {
-            formatted.push('.');
-        }
-        formatted.push(ch);
-        count += 1;
-    }
-
-    formatted = formatted.chars().rev().collect();
-    format!("${}", formatted)
+fn negro() -> Color {
+    Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None))
 }
 
-pub fn set_linea_horizontal(y: f32) -> Box<dyn Fn(f32) -> Polygon> {
-    // let i:f32  = (resources.page_height - altura) as f32;
-    let poligono: Box<dyn Fn(f32) -> Polygon> = Box::new(move |height: f32| {
-        let i = height - y;
-        let l = 5.0;
-        let r = 75.0;
-        let points = vec![
-            (Point::new(Mm(l), Mm(i)), false),
-            (Point::new(Mm(l), Mm(i)), false),
-            (Point::new(Mm(r), Mm(i)), false),
-            (Point::new(Mm(r), Mm(i)), false),
-        ];
-
-        let line = Polygon {
-            rings: vec![points],
-            mode: PaintMode::FillStroke,
-            winding_order: WindingOrder::NonZero,
-        };
-        line
-    });
-    poligono
+/// Nivel de gris como color de relleno (0 = negro, 1 = blanco).
+fn tinta(gris: f32) -> Color {
+    Color::Greyscale(Greyscale::new(gris, None))
 }
 
-pub fn set_linea_vertical(
-    y1: f32,
-    y2: f32,
-) -> (
-    Box<dyn Fn(f32) -> Polygon>,
-    Box<dyn Fn(f32) -> Polygon>,
+/// Dibuja el ícono en su tamaño en mm; el BMP se lee recién aquí.
+fn draw_imagen(
+    current_layer: &PdfLayerReference,
+    imagen: &Imagen,
+    page_height: f32,
 ) {
-    let pol_l: Box<dyn Fn(f32) -> Polygon> = Box::new(move |height: f32| {
-        let r = 75.0;
-        let points_r = vec![
-            (
-                Point::new(Mm(r), Mm(height - y1)),
-                false,
-            ),
-            (
-                Point::new(Mm(r), Mm(height - y2)),
-                false,
-            ),
-        ];
-        let line_l = Polygon {
-            rings: vec![points_r],
-            mode: PaintMode::FillStroke,
-            winding_order: WindingOrder::NonZero,
-        };
-        line_l
-    });
-
-    let pol_r: Box<dyn Fn(f32) -> Polygon> = Box::new(move |height: f32| {
-        let l = 5.0;
-        let points_l = vec![
-            (
-                Point::new(Mm(l), Mm(height - y1)),
-                false,
-            ),
-            (
-                Point::new(Mm(l), Mm(height - y2)),
-                false,
-            ),
-        ];
-        let line_r = Polygon {
-            rings: vec![points_l],
-            mode: PaintMode::FillStroke,
-            winding_order: WindingOrder::NonZero,
-        };
-        line_r
-    });
-    (pol_l, pol_r)
+    fn px_to_mm(px: f32) -> f32 {
+        // There are 25.4 millimeters in an inch.
+        (px / DPI) * 25.4
+    }
+
+    let mut image_file = File::open(ruta_icono(&imagen.icono)).unwrap();
+    let img: Image = Image::try_from(
+        image_crate::codecs::bmp::BmpDecoder::new(&mut image_file).unwrap(),
+    )
+    .unwrap();
+
+    // scale_x * base_scale_x = mm_x
+    let scale_x = imagen.ancho / px_to_mm(img.image.width.0 as f32);
+    let scale_y = imagen.alto / px_to_mm(img.image.height.0 as f32);
+    img.add_to_layer(
+        current_layer.clone(),
+        ImageTransform {
+            translate_x: Some(Mm(imagen.x)),
+            translate_y: Some(Mm(page_height - imagen.y)),
+            rotate: None,
+            scale_x: Some(scale_x),
+            scale_y: Some(scale_y),
+            dpi: Some(DPI),
+        },
+    );
 }
 
 // pub fn set_texto(
@@ -614,3 +958,122 @@ pub fn set_linea_vertical(
 
 //     x_position
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Texto y ancho de la celda derecha de una fila.
+    fn celda_derecha(texto: &str, opciones: &OpcionesFila) -> (String, f32) {
+        let mut pdf = PdfResources::new();
+        pdf.set_row(
+            &Celda::bold("*NUESTRO*", 16.0),
+            &Celda::bold(texto, 40.0),
+            0.0,
+            opciones,
+        );
+        let linea = &pdf.paragraphs[0].lines[0];
+        // siempre alineada al margen derecho
+        let fin = linea.x_position + linea.width_mm;
+        assert!((fin - MARGEN_DERECHO).abs() < 0.01);
+        (linea.text.clone(), linea.width_mm)
+    }
+
+    #[test]
+    fn set_row_corta_la_celda_derecha_en_su_ancho() {
+        let opciones = OpcionesFila {
+            ancho_derecha: Some(40.0),
+            ..OpcionesFila::default()
+        };
+        assert_eq!(celda_derecha("P42", &opciones).0, "P42");
+        let (texto, ancho) = celda_derecha("P123456789", &opciones);
+        assert!(texto.starts_with("P1") && texto.ends_with('…'), "{}", texto);
+        assert!(ancho <= 40.0);
+    }
+
+    #[test]
+    fn set_row_sin_ancho_no_pasa_el_margen_izquierdo() {
+        let largo = "P".to_string() + &"9".repeat(40);
+        let (texto, ancho) = celda_derecha(&largo, &OpcionesFila::default());
+        assert!(texto.ends_with('…'));
+        assert!(ancho <= MARGEN_DERECHO - MARGEN_IZQUIERDO);
+    }
+
+    #[test]
+    fn set_row_parte_la_izquierda_sin_pisar_la_derecha() {
+        let mut pdf = PdfResources::new();
+        let y_final = pdf.set_row(
+            &Celda::light("Pizza familiar napolitana con doble queso", 16.0),
+            &Celda::bold("$12.990", 16.0),
+            10.0,
+            &OpcionesFila::default(),
+        );
+        let derecha = &pdf.paragraphs[0].lines[0];
+        let izquierda = &pdf.paragraphs[1];
+        assert!(izquierda.lines.len() > 1);
+        for linea in &izquierda.lines {
+            assert_eq!(linea.x_position, MARGEN_IZQUIERDO);
+            assert!(linea.x_position + linea.width_mm < derecha.x_position);
+        }
+        assert!(y_final > 10.0 && y_final == pdf.page_height);
+    }
+
+    #[test]
+    fn set_row_con_guias_entre_las_celdas() {
+        let mut pdf = PdfResources::new();
+        pdf.set_row(
+            &Celda::light("Efectivo", 14.0),
+            &Celda::bold("$3.000", 14.0),
+            0.0,
+            &OpcionesFila {
+                guias: true,
+                ..OpcionesFila::default()
+            },
+        );
+        let derecha = &pdf.paragraphs[0].lines[0];
+        let guia = &pdf.paragraphs[1].lines[0];
+        let izquierda = &pdf.paragraphs[2].lines[0];
+        assert!(!guia.text.is_empty() && guia.text.chars().all(|c| c == '.'));
+        assert!(guia.x_position > izquierda.x_position + izquierda.width_mm);
+        assert!(guia.x_position + guia.width_mm < derecha.x_position);
+    }
+
+    fn textos(lineas: &[ParrafoLine]) -> Vec<&str> {
+        lineas.iter().map(|linea| linea.text.as_str()).collect()
+    }
+
+    #[test]
+    fn partir_lineas_corta_palabras_largas_en_silabas() {
+        let pdf = PdfResources::new();
+        let ancho = 20.0;
+        let palabra = "Electrodomésticos";
+        let lineas = partir_lineas(palabra, 16.0, ancho, &pdf.bold);
+        assert!(lineas.len() > 1);
+        for linea in &lineas[..lineas.len() - 1] {
+            assert!(linea.text.ends_with('-'), "{}", linea.text);
+            assert!(!linea.fin_renglon);
+        }
+        assert!(lineas.iter().all(|linea| linea.width_mm <= ancho));
+        assert_eq!(textos(&lineas).concat().replace('-', ""), palabra);
+    }
+
+    #[test]
+    fn partir_lineas_corta_codigos_por_caracteres() {
+        let pdf = PdfResources::new();
+        let ancho = 15.0;
+        let codigo = "PROMO2024PROMO2024";
+        let lineas = partir_lineas(codigo, 16.0, ancho, &pdf.bold);
+        assert!(lineas.len() > 1);
+        assert!(lineas.iter().all(|linea| linea.width_mm <= ancho));
+        assert_eq!(textos(&lineas).concat(), codigo);
+    }
+
+    #[test]
+    fn partir_lineas_respeta_saltos_de_linea() {
+        let pdf = PdfResources::new();
+        let texto = "Sin cebolla\n\nTocar timbre";
+        let lineas = partir_lineas(texto, 12.0, 70.0, &pdf.light);
+        assert_eq!(textos(&lineas), vec!["Sin cebolla", "", "Tocar timbre"]);
+        assert!(lineas.iter().all(|linea| linea.fin_renglon));
+    }
+}
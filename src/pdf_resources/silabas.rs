@@ -0,0 +1,136 @@
+//! División en sílabas del español para cortar palabras con guion al final
+//! de una línea.
+
+/// Letras mínimas que deben quedar a cada lado del guion.
+const MINIMO_LETRAS: usize = 2;
+
+/// Pares de consonantes que no se separan: grupos con `l`/`r` (pla, bra,
+/// ...) y los dígrafos `ch`, `ll` y `rr`.
+const GRUPOS_INSEPARABLES: [&str; 17] = [
+    "pl", "bl", "cl", "gl", "fl", "kl", "pr", "br", "tr", "dr", "cr", "gr",
+    "fr", "kr", "ch", "ll", "rr",
+];
+
+fn es_vocal(c: char) -> bool {
+    "aeiouáéíóúü".contains(c)
+}
+
+fn inseparables(a: char, b: char) -> bool {
+    let par: String = [a, b].iter().collect();
+    GRUPOS_INSEPARABLES.contains(&par.as_str())
+}
+
+/// Posiciones (en bytes) de `palabra` donde se puede cortar con guion,
+/// entre sílabas. Solo se corta entre consonante y vocal o entre
+/// consonantes, nunca entre vocales (hiato), y sin dejar menos de
+/// `MINIMO_LETRAS` letras de la palabra a cada lado. Lo que no es una
+/// palabra (URLs, correos, códigos) no se corta: ahí el guion se
+/// confundiría con el texto.
+///
+/// Reglas para las consonantes entre dos vocales:
+///   - una: va con la vocal siguiente (ca-sa)
+///   - dos: se separan (can-to) salvo un grupo inseparable (co-bre)
+///   - tres o más: la última va con la vocal siguiente (ins-tan-te), o las
+///     dos últimas si son inseparables (cons-truir)
+pub(super) fn cortes_silaba(palabra: &str) -> Vec<usize> {
+    // se permite puntuación al inicio y al final ("favor!!!", "(promo)")
+    let nucleo = palabra.trim_matches(|c: char| !c.is_alphanumeric());
+    if nucleo.chars().any(|c| !c.is_alphabetic()) {
+        return Vec::new();
+    }
+    let letras: Vec<(usize, char)> = palabra
+        .char_indices()
+        .map(|(i, c)| (i, c.to_lowercase().next().unwrap_or(c)))
+        .collect();
+    let mut cortes = Vec::new();
+
+    let mut i = 0;
+    while i < letras.len() {
+        // buscamos una vocal seguida de consonantes y de otra vocal, todo
+        // dentro de la misma racha de letras
+        if !es_vocal(letras[i].1) {
+            i += 1;
+            continue;
+        }
+        let inicio = i + 1;
+        let mut fin = inicio;
+        while fin < letras.len()
+            && letras[fin].1.is_alphabetic()
+            && !es_vocal(letras[fin].1)
+        {
+            fin += 1;
+        }
+        let consonantes = fin - inicio;
+        if consonantes == 0 || fin == letras.len() || !es_vocal(letras[fin].1) {
+            i = fin.max(i + 1);
+            continue;
+        }
+
+        let corte = match consonantes {
+            1 => inicio,
+            _ if inseparables(letras[fin - 2].1, letras[fin - 1].1) => fin - 2,
+            _ => fin - 1,
+        };
+        let letras_antes = letras[..corte]
+            .iter()
+            .rev()
+            .take_while(|(_, c)| c.is_alphabetic())
+            .count();
+        let letras_despues = letras[corte..]
+            .iter()
+            .take_while(|(_, c)| c.is_alphabetic())
+            .count();
+        if letras_antes >= MINIMO_LETRAS && letras_despues >= MINIMO_LETRAS {
+            cortes.push(letras[corte].0);
+        }
+        i = fin;
+    }
+    cortes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// La palabra con un guion en cada corte posible.
+    fn con_guiones(palabra: &str) -> String {
+        let mut resultado = String::new();
+        let mut desde = 0;
+        for corte in cortes_silaba(palabra) {
+            resultado += &palabra[desde..corte];
+            resultado.push('-');
+            desde = corte;
+        }
+        resultado + &palabra[desde..]
+    }
+
+    #[test]
+    fn consonantes_entre_vocales() {
+        assert_eq!(con_guiones("casa"), "ca-sa");
+        assert_eq!(con_guiones("canto"), "can-to");
+        assert_eq!(con_guiones("cobre"), "co-bre");
+        assert_eq!(con_guiones("pollo"), "po-llo");
+        assert_eq!(con_guiones("instante"), "ins-tan-te");
+        assert_eq!(con_guiones("construir"), "cons-truir");
+    }
+
+    #[test]
+    fn hiatos_y_minimo_de_letras() {
+        assert_eq!(con_guiones("leer"), "leer");
+        assert_eq!(con_guiones("ala"), "ala");
+        assert_eq!(con_guiones("Camión"), "Ca-mión");
+    }
+
+    #[test]
+    fn cortes_en_bytes_con_acentos() {
+        assert_eq!(cortes_silaba("señora"), vec![2, 5]);
+        assert_eq!(con_guiones("señora"), "se-ño-ra");
+    }
+
+    #[test]
+    fn puntuacion_y_codigos() {
+        assert_eq!(con_guiones("favor!!!"), "fa-vor!!!");
+        assert!(cortes_silaba("www.agil.cl").is_empty());
+        assert!(cortes_silaba("PROMO2024").is_empty());
+    }
+}
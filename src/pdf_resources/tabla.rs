@@ -0,0 +1,260 @@
+use super::{
+    alinear_lineas, medir_texto_mm, partir_lineas, Alineacion, Caja, Celda,
+    EstiloLinea, EstiloTexto, PdfResources, MARGEN_DERECHO, MARGEN_IZQUIERDO,
+};
+
+/// La tabla ocupa el ancho útil del papel.
+const ANCHO_TABLA: f32 = MARGEN_DERECHO - MARGEN_IZQUIERDO;
+/// Espacio entre columnas, en mm.
+const ESPACIO_COLUMNAS: f32 = 2.0;
+/// Espacio extra entre filas, en mm.
+const ESPACIO_FILAS: f32 = 2.0;
+
+/// Ancho de una columna de tabla.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnchoColumna {
+    /// Ancho fijo en mm.
+    Fijo(f32),
+    /// El del texto más ancho de la columna (encabezado incluido).
+    Auto,
+    /// Parte proporcional del ancho que sobra tras las columnas fijas y
+    /// automáticas.
+    Fraccion(f32),
+}
+
+pub struct Columna {
+    pub titulo: String,
+    pub ancho: AnchoColumna,
+    pub alineacion: Alineacion,
+}
+
+/// Sección tabular (p.ej. cantidad | detalle | unitario | total). Las
+/// celdas que no caben en su columna se parten en varias líneas.
+pub struct Tabla {
+    pub columnas: Vec<Columna>,
+    pub filas: Vec<Vec<String>>,
+    pub font_size: f32,
+    /// Estilo del encabezado, que va en bold y con una línea debajo.
+    pub estilo_encabezado: EstiloTexto,
+    /// Línea entre filas.
+    pub separadores: bool,
+}
+
+impl PdfResources<'_> {
+    /// Agrega la tabla desde `y_inicial` y devuelve la posición Y donde
+    /// termina.
+    pub fn set_tabla(&mut self, tabla: &Tabla, y_inicial: f32) -> f32 {
+        let anchos = self.anchos_columnas(tabla);
+
+        let titulos: Vec<&str> =
+            tabla.columnas.iter().map(|c| c.titulo.as_str()).collect();
+        let mut y_actual = self.set_fila_tabla(
+            tabla,
+            &anchos,
+            &titulos,
+            false,
+            tabla.estilo_encabezado,
+            y_inicial,
+        );
+        self.set_linea(y_actual + ESPACIO_FILAS / 2.0);
+
+        for (i, fila) in tabla.filas.iter().enumerate() {
+            if i > 0 && tabla.separadores {
+                self.set_linea_estilo(
+                    y_actual + ESPACIO_FILAS / 2.0,
+                    EstiloLinea::punteada(0.35),
+                );
+            }
+            let celdas: Vec<&str> = fila.iter().map(String::as_str).collect();
+            y_actual = self.set_fila_tabla(
+                tabla,
+                &anchos,
+                &celdas,
+                true,
+                EstiloTexto::Normal,
+                y_actual + ESPACIO_FILAS,
+            );
+        }
+        y_actual
+    }
+
+    /// Resuelve el ancho de cada columna: primero las fijas y automáticas
+    /// (achicando las automáticas si no caben) y luego se reparte el resto
+    /// entre las fraccionarias.
+    fn anchos_columnas(&self, tabla: &Tabla) -> Vec<f32> {
+        let espacios =
+            ESPACIO_COLUMNAS * tabla.columnas.len().saturating_sub(1) as f32;
+        let mut anchos: Vec<f32> = tabla
+            .columnas
+            .iter()
+            .enumerate()
+            .map(|(i, columna)| match columna.ancho {
+                AnchoColumna::Fijo(ancho) => ancho,
+                AnchoColumna::Auto => {
+                    let titulo = medir_texto_mm(
+                        &columna.titulo,
+                        tabla.font_size,
+                        &self.bold,
+                    );
+                    tabla
+                        .filas
+                        .iter()
+                        .filter_map(|fila| fila.get(i))
+                        .map(|celda| {
+                            medir_texto_mm(celda, tabla.font_size, &self.light)
+                        })
+                        .fold(titulo, f32::max)
+                }
+                AnchoColumna::Fraccion(_) => 0.0,
+            })
+            .collect();
+
+        let (mut fijos, mut autos) = (0.0, 0.0);
+        for (ancho, columna) in anchos.iter().zip(&tabla.columnas) {
+            match columna.ancho {
+                AnchoColumna::Fijo(_) => fijos += ancho,
+                AnchoColumna::Auto => autos += ancho,
+                AnchoColumna::Fraccion(_) => {}
+            }
+        }
+        let disponible_autos = (ANCHO_TABLA - espacios - fijos).max(0.0);
+        if autos > disponible_autos {
+            let escala = disponible_autos / autos;
+            for (ancho, columna) in anchos.iter_mut().zip(&tabla.columnas) {
+                if columna.ancho == AnchoColumna::Auto {
+                    *ancho *= escala;
+                }
+            }
+        }
+
+        let resto =
+            (ANCHO_TABLA - espacios - anchos.iter().sum::<f32>()).max(0.0);
+        let fracciones: f32 = tabla
+            .columnas
+            .iter()
+            .filter_map(|columna| match columna.ancho {
+                AnchoColumna::Fraccion(fraccion) => Some(fraccion),
+                _ => None,
+            })
+            .sum();
+        for (ancho, columna) in anchos.iter_mut().zip(&tabla.columnas) {
+            if let AnchoColumna::Fraccion(fraccion) = columna.ancho {
+                if fracciones > 0.0 {
+                    *ancho = resto * fraccion / fracciones;
+                }
+            }
+        }
+        anchos
+    }
+
+    /// Agrega una fila con cada celda partida en el ancho de su columna. El
+    /// alto de la fila es el de la celda con más líneas.
+    fn set_fila_tabla(
+        &mut self,
+        tabla: &Tabla,
+        anchos: &[f32],
+        celdas: &[&str],
+        light: bool,
+        estilo: EstiloTexto,
+        y_inicial: f32,
+    ) -> f32 {
+        let mut y_final = y_inicial;
+        let mut x_columna = MARGEN_IZQUIERDO;
+        for ((columna, ancho), texto) in
+            tabla.columnas.iter().zip(anchos).zip(celdas)
+        {
+            let font_data = self.fuente(light);
+            let mut lineas = partir_lineas(
+                texto,
+                tabla.font_size,
+                *ancho,
+                font_data,
+            );
+            let caja = Caja {
+                alineacion: columna.alineacion,
+                ..Caja::izquierda(x_columna, *ancho)
+            };
+            alinear_lineas(&mut lineas, &caja, tabla.font_size, font_data);
+            let celda = Celda {
+                texto,
+                font_size: tabla.font_size,
+                light,
+                estilo,
+            };
+            let y_celda =
+                self.agregar_lineas(lineas, &celda, Some(caja), y_inicial);
+            y_final = y_final.max(y_celda);
+            x_columna += ancho + ESPACIO_COLUMNAS;
+        }
+        y_final
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tabla(anchos: &[AnchoColumna], fila: &[&str]) -> Tabla {
+        Tabla {
+            columnas: anchos
+                .iter()
+                .map(|&ancho| Columna {
+                    titulo: String::new(),
+                    ancho,
+                    alineacion: Alineacion::Izquierda,
+                })
+                .collect(),
+            filas: vec![fila.iter().map(|celda| celda.to_string()).collect()],
+            font_size: 11.0,
+            estilo_encabezado: EstiloTexto::Normal,
+            separadores: false,
+        }
+    }
+
+    fn cerca(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn fracciones_reparten_lo_que_sobra() {
+        let pdf = PdfResources::new();
+        let tabla = tabla(
+            &[
+                AnchoColumna::Fijo(10.0),
+                AnchoColumna::Fraccion(1.0),
+                AnchoColumna::Fraccion(3.0),
+            ],
+            &["1", "Pizza", "Bebida"],
+        );
+        let anchos = pdf.anchos_columnas(&tabla);
+        let resto = ANCHO_TABLA - 2.0 * ESPACIO_COLUMNAS - 10.0;
+        assert!(cerca(anchos[0], 10.0));
+        assert!(cerca(anchos[1], resto / 4.0));
+        assert!(cerca(anchos[2], resto * 3.0 / 4.0));
+    }
+
+    #[test]
+    fn auto_toma_el_texto_mas_ancho() {
+        let pdf = PdfResources::new();
+        let tabla = tabla(
+            &[AnchoColumna::Auto, AnchoColumna::Fraccion(1.0)],
+            &["$12.990", "Pizza"],
+        );
+        let anchos = pdf.anchos_columnas(&tabla);
+        let texto = medir_texto_mm("$12.990", 11.0, &pdf.light);
+        assert!(cerca(anchos[0], texto));
+        assert!(cerca(anchos[0] + ESPACIO_COLUMNAS + anchos[1], ANCHO_TABLA));
+    }
+
+    #[test]
+    fn auto_se_achica_si_no_cabe() {
+        let pdf = PdfResources::new();
+        let largo = "palabra ".repeat(20);
+        let tabla = tabla(
+            &[AnchoColumna::Fijo(20.0), AnchoColumna::Auto],
+            &["1", &largo],
+        );
+        let anchos = pdf.anchos_columnas(&tabla);
+        assert!(cerca(anchos[1], ANCHO_TABLA - 20.0 - ESPACIO_COLUMNAS));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::pdf_resources::EstiloTexto;
+use crate::Plataforma;
+
+/// Cómo se presenta una plataforma en el encabezado del ticket.
+pub struct MarcaPlataforma {
+    pub codigo: &'static str,
+    /// Nombre que se imprime en lugar de `Plataforma.nombre`.
+    pub nombre: &'static str,
+    /// Nombre del logo en `assets/img` (sin extensión); ver `logo_opcional`.
+    pub logo: Option<&'static str>,
+    /// Estilo del nombre: las plataformas externas van en banda invertida
+    /// para distinguirlas de un vistazo.
+    pub estilo: EstiloTexto,
+    /// Etiqueta del id de la orden en la plataforma, si se imprime.
+    pub etiqueta_id: Option<&'static str>,
+}
+
+/// Plataformas conocidas, por `Plataforma.codigo`.
+static PLATAFORMAS: [MarcaPlataforma; 5] = [
+    MarcaPlataforma {
+        codigo: "AGIL",
+        nombre: "Agil",
+        logo: None,
+        estilo: EstiloTexto::Normal,
+        etiqueta_id: None,
+    },
+    MarcaPlataforma {
+        codigo: "RAPPI",
+        nombre: "Rappi",
+        logo: Some("plataformas/rappi"),
+        estilo: EstiloTexto::Invertido,
+        etiqueta_id: Some("ID Rappi"),
+    },
+    MarcaPlataforma {
+        codigo: "UBER",
+        nombre: "Uber Eats",
+        logo: Some("plataformas/uber"),
+        estilo: EstiloTexto::Invertido,
+        etiqueta_id: Some("ID Uber"),
+    },
+    MarcaPlataforma {
+        codigo: "PEDIDOSYA",
+        nombre: "PedidosYa",
+        logo: Some("plataformas/pedidosya"),
+        estilo: EstiloTexto::Invertido,
+        etiqueta_id: Some("ID PedidosYa"),
+    },
+    MarcaPlataforma {
+        codigo: "DIDI",
+        nombre: "DiDi Food",
+        logo: Some("plataformas/didi"),
+        estilo: EstiloTexto::Invertido,
+        etiqueta_id: Some("ID DiDi"),
+    },
+];
+
+/// Busca la marca de la plataforma por su código, sin distinguir
+/// mayúsculas.
+pub fn marca_plataforma(
+    plataforma: &Plataforma,
+) -> Option<&'static MarcaPlataforma> {
+    let codigo = plataforma.codigo.as_ref()?.trim().to_uppercase();
+    PLATAFORMAS.iter().find(|marca| marca.codigo == codigo)
+}
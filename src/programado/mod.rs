@@ -0,0 +1,175 @@
+use chrono::{DateTime, Datelike, FixedOffset, Weekday};
+use chrono_tz::Tz;
+
+use crate::pdf_resources::format_datetime;
+use crate::{Fechas, IOrder};
+
+/// Minutos entre el pago y la entrega a partir de los cuales una orden se
+/// considera programada.
+pub const UMBRAL_PROGRAMADO_MIN: i64 = 90;
+
+fn parse_fecha(iso_date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(iso_date).expect("Error parsing ISO date")
+}
+
+/// Indica si la orden es programada.
+///
+/// Manda el flag explícito de la orden; si no viene, la orden es programada
+/// cuando el inicio de la ventana de entrega está a más de `umbral_min`
+/// minutos de la hora de pago.
+pub fn es_programado(orden: &IOrder, umbral_min: i64) -> bool {
+    if let Some(programado) = orden.entrega_programada {
+        return programado;
+    }
+    let pago = parse_fecha(&orden.fechas.fecha_pago);
+    let entrega = parse_fecha(&orden.fechas.fecha_entrega_min);
+    (entrega - pago).num_minutes() > umbral_min
+}
+
+fn nombre_dia(dia: Weekday) -> &'static str {
+    match dia {
+        Weekday::Mon => "Lunes",
+        Weekday::Tue => "Martes",
+        Weekday::Wed => "Miércoles",
+        Weekday::Thu => "Jueves",
+        Weekday::Fri => "Viernes",
+        Weekday::Sat => "Sábado",
+        Weekday::Sun => "Domingo",
+    }
+}
+
+/// Lleva `fecha` a la zona horaria `tz` del comercio ("America/Santiago");
+/// si no se reconoce, al offset `respaldo`.
+fn en_zona(
+    fecha: DateTime<FixedOffset>,
+    tz: &str,
+    respaldo: &FixedOffset,
+) -> DateTime<FixedOffset> {
+    match tz.parse::<Tz>() {
+        Ok(zona) => fecha.with_timezone(&zona).fixed_offset(),
+        Err(_) => fecha.with_timezone(respaldo),
+    }
+}
+
+/// Día de entrega relativo al de la impresión (`fecha_referencia`): "Hoy",
+/// "Mañana", el nombre del día si es dentro de la semana, o el día con la
+/// fecha ("Lunes 06 Ene").
+///
+/// Los días se cortan a medianoche en la zona horaria `tz` del comercio
+/// ("America/Santiago"); si no se reconoce, en la zona de la entrega.
+pub fn etiqueta_dia(fecha_referencia: &str, fecha: &str, tz: &str) -> String {
+    let entrega = parse_fecha(fecha);
+    let entrega = en_zona(entrega, tz, entrega.offset());
+    let referencia = parse_fecha(fecha_referencia);
+    let referencia = en_zona(referencia, tz, entrega.offset());
+    let dia_entrega = entrega.date_naive();
+    let dias = (dia_entrega - referencia.date_naive()).num_days();
+    match dias {
+        0 => String::from("Hoy"),
+        1 => String::from("Mañana"),
+        2..=6 => nombre_dia(dia_entrega.weekday()).to_string(),
+        _ => {
+            nombre_dia(dia_entrega.weekday()).to_string()
+                + " "
+                + &format_datetime(&entrega.to_rfc3339()).0
+        }
+    }
+}
+
+/// Ventana horaria de entrega en la zona del comercio, p.ej.
+/// "14:30 - 15:00".
+pub fn ventana_entrega(fechas: &Fechas) -> String {
+    let hora = |fecha: &str| {
+        let fecha = parse_fecha(fecha);
+        let local = en_zona(fecha, &fechas.tz, fecha.offset());
+        format_datetime(&local.to_rfc3339()).1
+    };
+    let inicio = hora(&fechas.fecha_entrega_min);
+    match fechas.fecha_entrega_max.as_ref() {
+        Some(fecha_max) => inicio + " - " + &hora(fecha_max),
+        None => inicio,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etiqueta_dia_contra_la_impresion() {
+        let tz = "America/Santiago";
+        let entrega = "2024-12-26T15:00:00Z";
+        assert_eq!(etiqueta_dia("2024-12-26T13:00:00Z", entrega, tz), "Hoy");
+        assert_eq!(
+            etiqueta_dia("2024-12-25T13:00:00Z", entrega, tz),
+            "Mañana"
+        );
+        assert_eq!(
+            etiqueta_dia("2024-12-23T13:00:00Z", entrega, tz),
+            "Jueves"
+        );
+    }
+
+    #[test]
+    fn etiqueta_dia_corta_en_la_zona_del_comercio() {
+        // 01:30 UTC del 27 sigue siendo el 26 en Santiago (UTC-3)
+        let entrega = "2024-12-27T01:30:00Z";
+        let impresion = "2024-12-26T14:00:00Z";
+        assert_eq!(etiqueta_dia(impresion, entrega, "America/Santiago"), "Hoy");
+        assert_eq!(etiqueta_dia(impresion, entrega, "UTC"), "Mañana");
+    }
+
+    #[test]
+    fn etiqueta_dia_con_fecha_que_cruza_medianoche() {
+        // 01:30 UTC del viernes 27 es el jueves 26 a las 22:30 en Santiago
+        let entrega = "2024-12-27T01:30:00Z";
+        let impresion = "2024-12-10T14:00:00Z";
+        assert_eq!(
+            etiqueta_dia(impresion, entrega, "America/Santiago"),
+            "Jueves 26 Dic"
+        );
+        assert_eq!(etiqueta_dia(impresion, entrega, "UTC"), "Viernes 27 Dic");
+    }
+
+    fn fechas(minima: &str, maxima: Option<&str>, tz: &str) -> Fechas {
+        Fechas {
+            fecha_salida_cocina_estimada: minima.to_string(),
+            fecha_entrega_min: minima.to_string(),
+            fecha_entrega_max: maxima.map(str::to_string),
+            fecha_pago: "2024-12-26T14:00:00Z".to_string(),
+            tz: tz.to_string(),
+        }
+    }
+
+    #[test]
+    fn ventana_entrega_en_la_zona_del_comercio() {
+        let ventana = fechas(
+            "2024-12-27T02:30:00Z",
+            Some("2024-12-27T03:15:00Z"),
+            "America/Santiago",
+        );
+        assert_eq!(ventana_entrega(&ventana), "23:30 - 00:15");
+        let sin_zona = fechas("2024-12-27T02:30:00-03:00", None, "");
+        assert_eq!(ventana_entrega(&sin_zona), "02:30");
+    }
+
+    #[test]
+    fn es_programado_segun_flag_o_umbral() {
+        let mut orden = crate::crear_orden_ejemplo();
+        orden.entrega_programada = None;
+        orden.fechas.fecha_pago = "2024-12-26T14:00:00Z".to_string();
+        orden.fechas.fecha_entrega_min = "2024-12-26T15:30:00Z".to_string();
+        assert!(!es_programado(&orden, UMBRAL_PROGRAMADO_MIN));
+        assert!(es_programado(&orden, 60));
+
+        orden.fechas.fecha_entrega_min = "2024-12-26T15:31:00Z".to_string();
+        assert!(es_programado(&orden, UMBRAL_PROGRAMADO_MIN));
+
+        // el flag de la orden manda sobre las fechas
+        orden.entrega_programada = Some(false);
+        assert!(!es_programado(&orden, UMBRAL_PROGRAMADO_MIN));
+        orden.fechas.fecha_entrega_min = "2024-12-26T14:10:00Z".to_string();
+        orden.entrega_programada = Some(true);
+        assert!(es_programado(&orden, UMBRAL_PROGRAMADO_MIN));
+    }
+}